@@ -0,0 +1,62 @@
+#![feature(test)]
+
+extern crate test;
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+use test::{black_box, Bencher};
+
+const LEN: usize = 4096;
+
+#[bench]
+fn append_byte(b: &mut Bencher) {
+    let mut backing = [MaybeUninit::uninit(); LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        for i in 0..LEN {
+            cursor.append_byte(black_box(i as u8));
+        }
+        black_box(buf.len())
+    });
+}
+
+#[bench]
+fn append_single_byte_slice(b: &mut Bencher) {
+    let mut backing = [MaybeUninit::uninit(); LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        for i in 0..LEN {
+            cursor.append(&[black_box(i as u8)]);
+        }
+        black_box(buf.len())
+    });
+}
+
+#[bench]
+fn append_uninit(b: &mut Bencher) {
+    let mut backing = [MaybeUninit::uninit(); LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        while cursor.capacity() >= 16 {
+            cursor.append(black_box(&[0xab; 16]));
+        }
+        black_box(buf.len())
+    });
+}
+
+// Appending into a buffer which is already initialized, e.g., when reusing a buffer.
+#[bench]
+fn append_init(b: &mut Bencher) {
+    let mut backing = [0; LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        while cursor.capacity() >= 16 {
+            cursor.append(black_box(&[0xab; 16]));
+        }
+        black_box(buf.len())
+    });
+}
@@ -0,0 +1,63 @@
+#![feature(test)]
+
+//! Benchmarks initializing a small, fixed number of bytes.
+//!
+//! Compares `ensure_init_n` against `ensure_init` on a cursor limited to the same size.
+
+extern crate test;
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+use test::{black_box, Bencher};
+
+const BACKING: usize = 64;
+
+fn dynamic(b: &mut Bencher, n: usize) {
+    let mut backing = [MaybeUninit::uninit(); BACKING];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled().take(black_box(n));
+        cursor.ensure_init();
+        black_box(cursor.init_mut().as_mut_ptr());
+    });
+}
+
+fn fixed<const N: usize>(b: &mut Bencher) {
+    let mut backing = [MaybeUninit::uninit(); BACKING];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init_n::<N>();
+        black_box(cursor.init_mut().as_mut_ptr());
+    });
+}
+
+#[bench]
+fn dynamic_2(b: &mut Bencher) {
+    dynamic(b, 2);
+}
+
+#[bench]
+fn fixed_2(b: &mut Bencher) {
+    fixed::<2>(b);
+}
+
+#[bench]
+fn dynamic_4(b: &mut Bencher) {
+    dynamic(b, 4);
+}
+
+#[bench]
+fn fixed_4(b: &mut Bencher) {
+    fixed::<4>(b);
+}
+
+#[bench]
+fn dynamic_8(b: &mut Bencher) {
+    dynamic(b, 8);
+}
+
+#[bench]
+fn fixed_8(b: &mut Bencher) {
+    fixed::<8>(b);
+}
@@ -0,0 +1,43 @@
+#![feature(test)]
+
+//! Benchmarks the hot accessors which are `#[inline(always)]` by default.
+//!
+//! Compare `cargo bench --bench inline` with `cargo bench --bench inline --features no-inline`.
+
+extern crate test;
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+use test::{black_box, Bencher};
+
+const LEN: usize = 4096;
+
+// Simulates a reader which produces a few bytes at a time.
+#[bench]
+fn small_reads(b: &mut Bencher) {
+    let mut backing = [MaybeUninit::uninit(); LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        while buf.len() < buf.capacity() {
+            let mut cursor = buf.unfilled();
+            let n = black_box(4).min(cursor.capacity());
+            cursor.append(&[0xab; 4][..n]);
+        }
+        black_box(buf.filled().len())
+    });
+}
+
+#[bench]
+fn advance(b: &mut Bencher) {
+    let mut backing = [0; LEN];
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        while buf.len() < buf.capacity() {
+            // SAFETY: the buffer was created from an initialized slice.
+            unsafe {
+                buf.unfilled().advance(black_box(1));
+            }
+        }
+        black_box(buf.init_len())
+    });
+}
@@ -0,0 +1,63 @@
+#![feature(test)]
+
+//! Benchmarks bulk initialization and copying into buffers.
+//!
+//! Compare `cargo bench --bench simd` with `cargo bench --bench simd --features simd`.
+
+extern crate test;
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+use test::{black_box, Bencher};
+
+fn ensure_init(b: &mut Bencher, len: usize) {
+    let mut backing = vec![MaybeUninit::uninit(); len];
+    b.bytes = len as u64;
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init();
+        black_box(cursor.init_mut().as_mut_ptr());
+    });
+}
+
+fn append(b: &mut Bencher, len: usize) {
+    let mut backing = vec![MaybeUninit::uninit(); len];
+    let data = vec![0xab; len];
+    b.bytes = len as u64;
+    b.iter(|| {
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(black_box(&data));
+        black_box(buf.len())
+    });
+}
+
+#[bench]
+fn ensure_init_4k(b: &mut Bencher) {
+    ensure_init(b, 4 << 10);
+}
+
+#[bench]
+fn ensure_init_64k(b: &mut Bencher) {
+    ensure_init(b, 64 << 10);
+}
+
+#[bench]
+fn ensure_init_1m(b: &mut Bencher) {
+    ensure_init(b, 1 << 20);
+}
+
+#[bench]
+fn append_4k(b: &mut Bencher) {
+    append(b, 4 << 10);
+}
+
+#[bench]
+fn append_64k(b: &mut Bencher) {
+    append(b, 64 << 10);
+}
+
+#[bench]
+fn append_1m(b: &mut Bencher) {
+    append(b, 1 << 20);
+}
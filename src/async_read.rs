@@ -0,0 +1,63 @@
+//! Filling buffers from readers which implement `futures::io::AsyncRead`.
+
+use crate::BorrowCursor;
+use futures::io::AsyncRead;
+use std::future;
+use std::io;
+use std::pin::Pin;
+
+/// Reads from `r` into the cursor once, advancing it by the number of bytes read.
+///
+/// The whole cursor is initialized before reading, since `AsyncRead::poll_read` requires an
+/// initialized buffer. Returns the number of bytes read; as for `AsyncRead::poll_read`, zero
+/// means the end of the input (or that the cursor is full).
+pub async fn read_buf_async<R: AsyncRead + Unpin + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+) -> io::Result<usize> {
+    if cursor.is_full() {
+        return Ok(0);
+    }
+
+    let buf = cursor.ensure_init_mut();
+    let len = buf.len();
+    let n = future::poll_fn(|cx| Pin::new(&mut *r).poll_read(cx, buf)).await?;
+    assert!(
+        n <= len,
+        "reader returned more bytes than the buffer can hold"
+    );
+    unsafe {
+        // SAFETY: the whole cursor was initialized above
+        cursor.advance(n);
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BorrowBuf;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn read_from_async_cursor() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut reader = Cursor::new(vec![1, 2, 3, 4, 5, 6]);
+
+        let n = block_on(read_buf_async(&mut reader, &mut buf.unfilled())).unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+
+        // A full cursor reads nothing.
+        let n = block_on(read_buf_async(&mut reader, &mut buf.unfilled())).unwrap();
+        assert_eq!(n, 0);
+
+        buf.clear();
+        let n = block_on(read_buf_async(&mut reader, &mut buf.unfilled())).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(buf.filled(), &[5, 6]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Filling buffers from readers which implement `embedded_io::Read` rather than `std::io::Read`.
+
+use crate::BorrowCursor;
+
+/// Reads from `r` into the cursor once, advancing it by the number of bytes read.
+///
+/// The whole cursor is initialized before reading, since `embedded_io::Read::read` requires an
+/// initialized buffer. Returns the number of bytes read; as for `embedded_io::Read::read`, zero
+/// means the end of the input (or that the cursor is full).
+pub fn read_buf<R: embedded_io::Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+) -> Result<usize, R::Error> {
+    if cursor.is_full() {
+        return Ok(0);
+    }
+
+    let buf = cursor.ensure_init_mut();
+    let len = buf.len();
+    let n = r.read(buf)?;
+    assert!(
+        n <= len,
+        "reader returned more bytes than the buffer can hold"
+    );
+    unsafe {
+        // SAFETY: the whole cursor was initialized above
+        cursor.advance(n);
+    }
+    Ok(n)
+}
+
+/// Extends `embedded_io::Read` with reading into a [`BorrowCursor`](crate::BorrowCursor).
+pub trait ReadBuf: embedded_io::Read {
+    /// Reads into the cursor once, see [`read_buf`].
+    fn read_buf(&mut self, cursor: &mut BorrowCursor<'_, '_>) -> Result<usize, Self::Error> {
+        read_buf(self, cursor)
+    }
+}
+
+impl<R: embedded_io::Read + ?Sized> ReadBuf for R {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BorrowBuf;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn read_from_slice() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        assert_eq!(reader.read_buf(&mut cursor), Ok(4));
+        assert_eq!(reader.read_buf(&mut cursor), Ok(0));
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+
+        buf.clear();
+        assert_eq!(read_buf(&mut reader, &mut buf.unfilled()), Ok(2));
+        assert_eq!(buf.filled(), &[5, 6]);
+    }
+}
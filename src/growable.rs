@@ -0,0 +1,131 @@
+//! A borrowed buffer which asks for a larger slice when it runs out of space.
+
+use std::cmp;
+use std::mem::MaybeUninit;
+
+/// A borrowed buffer which grows by calling `F` for a larger backing slice.
+///
+/// When an append does not fit, `grow` is called with the total capacity needed, the filled bytes
+/// are copied into the returned slice, and the append continues there. The returned slice must
+/// live for `'a`, e.g., an allocation from an arena which outlives the buffer. The old slice is not
+/// given back; it stays borrowed until `'a` ends.
+///
+/// Only the filled bytes are carried over to the new slice, so bytes initialized but not filled in
+/// the old slice are forgotten.
+pub struct GrowableBorrowBuf<'a, F: FnMut(usize) -> &'a mut [MaybeUninit<u8>]> {
+    buf: &'a mut [MaybeUninit<u8>],
+    filled: usize,
+    initialized: usize,
+    grow: F,
+}
+
+impl<'a, F: FnMut(usize) -> &'a mut [MaybeUninit<u8>]> GrowableBorrowBuf<'a, F> {
+    /// Creates a new, empty buffer over `buf`, which calls `grow` when it needs more space.
+    pub fn new(buf: &'a mut [MaybeUninit<u8>], grow: F) -> GrowableBorrowBuf<'a, F> {
+        GrowableBorrowBuf {
+            buf,
+            filled: 0,
+            initialized: 0,
+            grow,
+        }
+    }
+
+    /// Returns the length of the current backing slice.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the length of the filled part of the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.filled
+    }
+
+    /// Returns `true` if nothing has been filled.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Returns the length of the initialized part of the buffer.
+    #[inline]
+    pub fn init_len(&self) -> usize {
+        self.initialized
+    }
+
+    /// Returns a shared reference to the filled portion of the buffer.
+    #[inline]
+    pub fn filled(&self) -> &[u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[..self.filled]) }
+    }
+
+    /// Returns the filled portion of the buffer, for the whole of `'a`.
+    #[inline]
+    pub fn into_filled(self) -> &'a mut [u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf[..self.filled]) }
+    }
+
+    /// Appends `bytes` to the buffer, growing it first if they do not fit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice returned by the grow callback is smaller than the capacity asked for.
+    #[track_caller]
+    pub fn append(&mut self, bytes: &[u8]) {
+        let needed = self
+            .filled
+            .checked_add(bytes.len())
+            .expect("capacity overflow");
+        if needed > self.buf.len() {
+            self.grow_to(needed);
+        }
+
+        MaybeUninit::write_slice(&mut self.buf[self.filled..needed], bytes);
+        self.filled = needed;
+        self.initialized = cmp::max(self.initialized, needed);
+    }
+
+    #[track_caller]
+    fn grow_to(&mut self, needed: usize) {
+        let new = (self.grow)(needed);
+        assert!(
+            new.len() >= needed,
+            "grow callback returned {} bytes, but {} were needed",
+            new.len(),
+            needed
+        );
+
+        new[..self.filled].copy_from_slice(&self.buf[..self.filled]);
+        self.buf = new;
+        self.initialized = self.filled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_grows() {
+        let mut small = [MaybeUninit::uninit(); 4];
+        let mut large = [MaybeUninit::uninit(); 16];
+        let mut large = Some(&mut large[..]);
+        let mut requests = Vec::new();
+
+        let mut buf = GrowableBorrowBuf::new(&mut small[..], |needed| {
+            requests.push(needed);
+            large.take().expect("grew twice")
+        });
+        buf.append(&[1, 2, 3]);
+        assert_eq!(buf.capacity(), 4);
+
+        buf.append(&[4, 5, 6]);
+        assert_eq!(buf.capacity(), 16);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(buf.into_filled(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(requests, [6]);
+    }
+}
@@ -2,17 +2,70 @@
 #![feature(maybe_uninit_slice)]
 #![feature(maybe_uninit_write_slice)]
 #![feature(generic_associated_types)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
+#[cfg(feature = "futures")]
+pub mod async_read;
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+mod growable;
+mod limited;
+mod mem;
 pub mod owned;
+mod read;
+mod reassembly;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "zeroize")]
+mod zeroing;
 
+pub use growable::GrowableBorrowBuf;
+pub use limited::LimitedCursor;
+pub use read::{
+    fill_vec_from, read_array, read_buf, read_buf_exact, read_buf_until, read_buf_vectored,
+    read_buf_with_progress, ReadBuf,
+};
+pub use reassembly::ReassemblyBuf;
+#[cfg(feature = "zeroize")]
+pub use zeroing::ZeroingCursor;
+
+use std::borrow::Borrow;
 use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::mem::MaybeUninit;
+use std::ops::{Bound, Deref, DerefMut, Range, RangeBounds};
+
+/// Declares a `BorrowBuf` backed by an uninitialized array on the stack.
+///
+/// `borrow_buf!(buf, 1024);` declares a local `buf: BorrowBuf` with a capacity of 1024 bytes. The
+/// macro has to declare the array as a local too, so that it lives as long as the buffer, which is
+/// why it takes the name of the buffer rather than being used as an expression. The array is not
+/// accessible other than through the buffer.
+#[macro_export]
+macro_rules! borrow_buf {
+    ($name:ident, $capacity:expr) => {
+        let mut backing = [::std::mem::MaybeUninit::<u8>::uninit(); $capacity];
+        #[allow(unused_mut)]
+        let mut $name = $crate::BorrowBuf::from(&mut backing[..]);
+    };
+}
 
+/// A borrowed byte buffer which is incrementally filled and initialized.
+///
+/// `BorrowBuf` is `Send` and `Sync`, since it only holds a mutable reference to its backing slice.
 #[derive(Debug)]
 pub struct BorrowBuf<'a> {
     buf: &'a mut [MaybeUninit<u8>],
+    // The start of the buffer; bytes before it are headroom for `prepend`. `filled` and
+    // `initialized` are indices into `buf`, not relative to `head`.
+    head: usize,
     filled: usize,
     initialized: usize,
+    // The number of filled bytes which have been consumed via `Read` or `BufRead`.
+    consumed: usize,
 }
 
 /// Creates a new `BorrowBuf` from a fully initialized slice.
@@ -24,8 +77,10 @@ impl<'a> From<&'a mut [u8]> for BorrowBuf<'a> {
         BorrowBuf {
             //SAFETY: initialized data never becoming uninitialized is an invariant of BorrowBuf
             buf: unsafe { (slice as *mut [u8]).as_uninit_slice_mut().unwrap() },
+            head: 0,
             filled: 0,
             initialized: len,
+            consumed: 0,
         }
     }
 }
@@ -36,228 +91,2840 @@ impl<'a> From<&'a mut [u8]> for BorrowBuf<'a> {
 impl<'a> From<&'a mut [MaybeUninit<u8>]> for BorrowBuf<'a> {
     #[inline]
     fn from(buf: &'a mut [MaybeUninit<u8>]) -> BorrowBuf<'a> {
+        BorrowBuf::new_const(buf)
+    }
+}
+
+/// Returns the filled portion of the buffer, see `BorrowBuf::into_filled_ref`.
+impl<'a> From<BorrowBuf<'a>> for &'a [u8] {
+    #[inline]
+    fn from(buf: BorrowBuf<'a>) -> &'a [u8] {
+        buf.into_filled_ref()
+    }
+}
+
+impl<'a> BorrowBuf<'a> {
+    /// Creates a new `BorrowBuf` from a fully uninitialized buffer, the same as `From`, but usable
+    /// in const contexts.
+    ///
+    /// Of the other methods, only those which just do arithmetic on the buffer's lengths (such as
+    /// `capacity`, `len`, and `remaining_capacity`) are `const`; those which form slices or write
+    /// to the buffer rely on operations which cannot be used in const contexts.
+    #[inline]
+    pub const fn new_const(buf: &'a mut [MaybeUninit<u8>]) -> BorrowBuf<'a> {
         BorrowBuf {
             buf,
+            head: 0,
             filled: 0,
             initialized: 0,
+            consumed: 0,
         }
     }
-}
 
-impl<'a> BorrowBuf<'a> {
-    /// Returns the total capacity of the buffer.
+    /// Creates a new `BorrowBuf` which is completely filled with the contents of `slice`.
+    ///
+    /// This is for editing existing data with the buffer's methods. In contrast, converting a
+    /// `&mut [u8]` into a `BorrowBuf` with `From` creates an empty buffer which treats `slice` as
+    /// initialized spare space.
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.buf.len()
+    pub fn from_filled_slice(slice: &'a mut [u8]) -> BorrowBuf<'a> {
+        let mut buf = BorrowBuf::from(slice);
+        buf.filled = buf.capacity();
+        buf
     }
 
-    /// Returns a shared reference to the filled portion of the buffer.
+    /// Creates a `BorrowBuf` from a pointer, a capacity, and filled and initialized lengths, e.g., for a buffer
+    /// provided over FFI.
+    ///
+    /// This is the inverse of `parts`.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be valid for reads and writes of `capacity` bytes, and the memory must not be accessed other than
+    ///   through the returned buffer for `'a`; see `slice::from_raw_parts_mut`.
+    /// - The first `initialized` bytes must be initialized.
+    /// - `filled <= initialized <= capacity`.
     #[inline]
-    pub fn filled(&self) -> &[u8] {
-        //SAFETY: We only slice the filled part of the buffer, which is always valid
-        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[0..self.filled]) }
+    pub unsafe fn from_raw_parts(
+        ptr: *mut MaybeUninit<u8>,
+        capacity: usize,
+        filled: usize,
+        initialized: usize,
+    ) -> BorrowBuf<'a> {
+        debug_assert!(
+            filled <= initialized && initialized <= capacity,
+            "from_raw_parts requires filled <= initialized <= capacity"
+        );
+        BorrowBuf {
+            buf: std::slice::from_raw_parts_mut(ptr, capacity),
+            head: 0,
+            filled,
+            initialized,
+            consumed: 0,
+        }
     }
 
-    /// Returns the length of the filled part of the buffer.
+    /// Creates a new, empty `BorrowBuf` which reserves the first `headroom` bytes of `buf` for
+    /// `prepend`.
+    ///
+    /// This is for building a payload before its header, when the size of the header depends on
+    /// the payload. The headroom is not part of the buffer until bytes are prepended into it, so
+    /// the buffer's capacity is `buf.len() - headroom`. The headroom is zeroed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `headroom` is greater than `buf.len()`.
     #[inline]
-    pub fn len(&self) -> usize {
-        self.filled
-    }
+    #[track_caller]
+    pub fn with_headroom(buf: &'a mut [MaybeUninit<u8>], headroom: usize) -> BorrowBuf<'a> {
+        assert!(
+            headroom <= buf.len(),
+            "headroom of {} bytes is larger than the buffer of {} bytes",
+            headroom,
+            buf.len()
+        );
+        mem::zero(&mut buf[..headroom]);
 
-    /// Returns the length of the initialized part of the buffer.
-    #[inline]
-    pub fn init_len(&self) -> usize {
-        self.initialized
+        BorrowBuf {
+            buf,
+            head: headroom,
+            filled: headroom,
+            initialized: headroom,
+            consumed: 0,
+        }
     }
 
-    /// Returns a cursor over the unfilled part of the buffer.
+    /// Creates a new `BorrowBuf` from a fully uninitialized buffer, checking that it can hold at
+    /// least `min` bytes.
+    ///
+    /// This is useful for checking up front that a buffer is large enough for, e.g., a fixed-size
+    /// header, rather than panicking part way through filling it.
     #[inline]
-    pub fn unfilled<'b>(&'b mut self) -> BorrowCursor<'a, 'b> {
-        BorrowCursor { buf: self }
+    pub fn try_with_min_capacity(
+        buf: &'a mut [MaybeUninit<u8>],
+        min: usize,
+    ) -> Result<BorrowBuf<'a>, CapacityError> {
+        if buf.len() < min {
+            return Err(CapacityError {
+                requested: min,
+                available: buf.len(),
+            });
+        }
+
+        Ok(buf.into())
     }
 
-    /// Clears the buffer, resetting the filled region to empty.
+    /// Returns the total capacity of the buffer.
     ///
-    /// The number of initialized bytes is not changed, and the contents of the buffer are not modified.
+    /// Any remaining headroom (see `with_headroom`) is not included.
+    // A load and a subtract; not inlining it costs more than the body.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub const fn capacity(&self) -> usize {
+        self.buf.len() - self.head
+    }
+
+    /// Returns the number of bytes which can still be prepended to the buffer.
     #[inline]
-    pub fn clear(&mut self) -> &mut Self {
-        self.filled = 0;
-        self
+    pub const fn headroom(&self) -> usize {
+        self.head
     }
 
-    /// Asserts that the first `n` bytes of the buffer are initialized.
+    /// Writes `bytes` into the headroom, immediately before the filled part of the buffer.
     ///
-    /// `BorrowBuf` assumes that bytes are never de-initialized, so this method does nothing when called with fewer
-    /// bytes than are already known to be initialized.
+    /// If some of the buffer has already been consumed via `Read` or `BufRead`, the read position
+    /// stays at the same byte, so the prepended bytes are not read. Otherwise, reading starts with
+    /// the prepended bytes.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// The caller must ensure that the first `n` unfilled bytes of the buffer have already been initialized.
+    /// Panics if `bytes.len()` is greater than `self.headroom()`.
     #[inline]
-    pub unsafe fn set_init(&mut self, n: usize) -> &mut Self {
-        self.initialized = cmp::max(self.initialized, n);
-        self
+    #[track_caller]
+    pub fn prepend(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.head,
+            "cannot prepend {} bytes, only {} bytes of headroom left",
+            bytes.len(),
+            self.head
+        );
+
+        self.head -= bytes.len();
+        MaybeUninit::write_slice(&mut self.buf[self.head..][..bytes.len()], bytes);
+        if self.consumed > 0 {
+            self.consumed += bytes.len();
+        }
     }
-}
 
-/// A cursor view of a [`BorrowBuf`](BorrowBuf).
-///
-/// Provides mutable access to the unfilled portion (both initialised and uninitialised data) from
-/// the buffer.
-#[derive(Debug)]
-pub struct BorrowCursor<'a, 'b> {
-    buf: &'b mut BorrowBuf<'a>,
-}
+    /// Returns a shared reference to the filled portion of the buffer.
+    // Forms a slice from two fields; inlining lets the caller elide bounds checks on the result.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub fn filled(&self) -> &[u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[self.head..self.filled]) }
+    }
 
-impl<'a, 'b> BorrowCursor<'a, 'b> {
-    fn plone<'c>(&'c mut self) -> BorrowCursor<'a, 'c> {
-        BorrowCursor { buf: self.buf }
+    /// Returns a mutable reference to the filled portion of the buffer.
+    #[inline]
+    pub fn filled_mut(&mut self) -> &mut [u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf[self.head..self.filled]) }
     }
 
-    /// Returns the available space in the cursor.
+    /// Consumes the buffer, returning a shared reference to the filled portion for the whole of `'a`.
+    ///
+    /// This lets a function fill a caller's slice and return a read-only view of exactly what was filled.
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.buf.capacity() - self.buf.filled
+    pub fn into_filled_ref(self) -> &'a [u8] {
+        let buf: &'a [MaybeUninit<u8>] = self.buf;
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_ref(&buf[self.head..self.filled]) }
     }
 
     /// Returns a shared reference to the initialized portion of the buffer.
+    ///
+    /// Unlike `filled`, this includes bytes which are initialized but not filled, e.g., bytes left
+    /// over from before the buffer was cleared or zeroed by `ensure_init`.
     #[inline]
-    pub fn init_ref(&self) -> &[u8] {
+    pub fn initialized(&self) -> &[u8] {
         //SAFETY: We only slice the initialized part of the buffer, which is always valid
-        unsafe {
-            MaybeUninit::slice_assume_init_ref(&self.buf.buf[self.buf.filled..self.buf.initialized])
-        }
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[self.head..self.initialized]) }
     }
 
-    /// Returns a mutable reference to the initialized portion of the buffer.
+    /// Returns a mutable reference to the initialized but unfilled portion of the buffer.
+    ///
+    /// This is the same region as `BorrowCursor::init_mut` on a fresh cursor, e.g., for filling it with a canary
+    /// pattern before a read so that bytes the reader did not write can be detected.
     #[inline]
-    pub fn init_mut(&mut self) -> &mut [u8] {
+    pub fn initialized_unfilled_mut(&mut self) -> &mut [u8] {
         //SAFETY: We only slice the initialized part of the buffer, which is always valid
-        unsafe {
-            MaybeUninit::slice_assume_init_mut(
-                &mut self.buf.buf[self.buf.filled..self.buf.initialized],
-            )
-        }
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf[self.filled..self.initialized]) }
     }
 
-    /// Returns a mutable reference to the uninitialized part of the buffer.
+    /// Returns a shared reference to a sub-range of the filled portion of the buffer.
     ///
-    /// It is safe to uninitialize any of these bytes.
+    /// # Panics
+    ///
+    /// Panics if the range is not within the filled portion of the buffer, or if the start of the
+    /// range is after its end.
     #[inline]
-    pub fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
-        &mut self.buf.buf[self.buf.initialized..]
+    #[track_caller]
+    pub fn filled_range(&self, range: impl RangeBounds<usize>) -> &[u8] {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.checked_add(1).expect("range start overflows usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.checked_add(1).expect("range end overflows usize"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len(),
+        };
+
+        assert!(
+            end <= self.len(),
+            "range end index {} out of range for filled region of length {}",
+            end,
+            self.len()
+        );
+        assert!(
+            start <= end,
+            "range starts at {} but ends at {}",
+            start,
+            end
+        );
+
+        &self.filled()[start..end]
     }
 
-    /// A view of the cursor as a mutable slice of `MaybeUninit<u8>`.
+    /// Returns the filled byte at index `i`, or `None` if `i` is not in the filled part of the buffer.
     #[inline]
-    pub unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
-        &mut self.buf.buf[self.buf.filled..]
+    pub fn get_filled(&self, i: usize) -> Option<u8> {
+        self.filled().get(i).copied()
     }
 
-    /// Increases the size of the filled region of the buffer.
+    /// Overwrites the filled byte at index `i` with `b`, e.g., for patching a flags field.
     ///
-    /// # Safety
+    /// Returns `false`, without changing the buffer, if `i` is not in the filled part of the buffer.
+    #[inline]
+    pub fn set_filled(&mut self, i: usize, b: u8) -> bool {
+        match self.filled_mut().get_mut(i) {
+            Some(byte) => {
+                *byte = b;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the first filled byte, or `None` if the buffer is empty.
+    #[inline]
+    pub fn filled_first(&self) -> Option<u8> {
+        self.filled().first().copied()
+    }
+
+    /// Returns the last filled byte, or `None` if the buffer is empty.
+    #[inline]
+    pub fn filled_last(&self) -> Option<u8> {
+        self.filled().last().copied()
+    }
+
+    /// Removes the last filled byte and returns it, or `None` if the buffer is empty.
     ///
-    /// The caller must ensure that the first `n` elements of the cursor have been properly
-    /// initialised.
+    /// The byte stays initialized.
     #[inline]
-    pub unsafe fn advance(&mut self, n: usize) -> &mut Self {
-        self.buf.filled += n;
-        self.buf.initialized = cmp::max(self.buf.initialized, self.buf.filled);
-        self
+    pub fn pop_filled(&mut self) -> Option<u8> {
+        let last = self.filled_last()?;
+        self.filled -= 1;
+        self.consumed = cmp::min(self.consumed, self.len());
+        Some(last)
     }
 
-    /// Initialised all bytes in the cursor.
+    /// Returns an iterator over all overlapping windows of `size` filled bytes.
+    ///
+    /// This is the same as `self.filled().windows(size)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
     #[inline]
-    pub fn ensure_init(&mut self) -> &mut Self {
-        for byte in self.uninit_mut() {
-            byte.write(0);
-        }
-        self.buf.initialized = self.buf.capacity();
+    #[track_caller]
+    pub fn filled_windows(&self, size: usize) -> std::slice::Windows<'_, u8> {
+        self.filled().windows(size)
+    }
 
-        self
+    /// Returns the index of the first filled byte equal to `needle`.
+    #[cfg(feature = "memchr")]
+    #[inline]
+    pub fn find_filled(&self, needle: u8) -> Option<usize> {
+        memchr::memchr(needle, self.filled())
     }
 
-    /// Asserts that the first `n` unfilled bytes of the cursor are initialized.
+    /// Returns the index of the first filled byte equal to `needle`, starting the search at index `start`.
     ///
-    /// `BorrowBuf` assumes that bytes are never de-initialized, so this method does nothing when called with fewer
-    /// bytes than are already known to be initialized.
+    /// The index returned is relative to the start of the filled part, not to `start`. Returns `None` if `start` is
+    /// past the end of the filled part.
+    #[cfg(feature = "memchr")]
+    #[inline]
+    pub fn find_filled_from(&self, needle: u8, start: usize) -> Option<usize> {
+        let rest = self.filled().get(start..)?;
+        memchr::memchr(needle, rest).map(|i| start + i)
+    }
+
+    /// Returns the index of the last filled byte equal to `needle`.
+    #[cfg(feature = "memchr")]
+    #[inline]
+    pub fn rfind_filled(&self, needle: u8) -> Option<usize> {
+        memchr::memrchr(needle, self.filled())
+    }
+
+    /// Overwrites filled bytes starting at `offset` with `bytes`.
     ///
-    /// # Safety
+    /// This is useful for writing a length prefix once the length is known: append a placeholder,
+    /// append the body, then backpatch the placeholder.
     ///
-    /// The caller must ensure that the first `n` bytes of the buffer have already been initialized.
+    /// # Panics
+    ///
+    /// Panics if `offset + bytes.len()` is greater than `self.len()`.
     #[inline]
-    pub unsafe fn set_init(&mut self, n: usize) -> &mut Self {
-        self.buf.initialized = cmp::max(self.buf.initialized, self.buf.filled + n);
-        self
+    #[track_caller]
+    pub fn backpatch(&mut self, offset: usize, bytes: &[u8]) {
+        let end = offset.checked_add(bytes.len());
+        assert!(
+            matches!(end, Some(end) if end <= self.len()),
+            "backpatch range out of range for filled region of length {}",
+            self.len()
+        );
+
+        self.filled_mut()[offset..offset + bytes.len()].copy_from_slice(bytes);
     }
 
-    /// Appends data to the cursor, advancing the position within its buffer.
+    /// Writes `data` at `offset` in the buffer, which need not be at the end of the filled part.
+    ///
+    /// The written bytes become initialized, but the filled length is not changed, since there may be gaps before
+    /// `offset`. If `offset` is past the initialized part of the buffer, the gap is zeroed so that the initialized
+    /// part stays contiguous. See [`ReassemblyBuf`] for tracking which bytes have been written.
     ///
     /// # Panics
     ///
-    /// Panics if `self.capacity()` is less than `buf.len()`.
+    /// Panics if `offset + data.len()` is greater than `self.capacity()`.
     #[inline]
-    pub fn append(&mut self, buf: &[u8]) {
-        assert!(self.capacity() >= buf.len());
+    #[track_caller]
+    pub fn write_at(&mut self, offset: usize, data: &[u8]) {
+        let end = offset.checked_add(data.len());
+        assert!(
+            matches!(end, Some(end) if end <= self.capacity()),
+            "write_at range out of range for buffer of capacity {}",
+            self.capacity()
+        );
 
-        // SAFETY: we do not de-initialize any of the elements of the slice
-        unsafe {
-            MaybeUninit::write_slice(&mut self.as_mut()[..buf.len()], buf);
+        let start = self.head + offset;
+        let end = start + data.len();
+        if start > self.initialized {
+            mem::zero(&mut self.buf[self.initialized..start]);
         }
+        MaybeUninit::write_slice(&mut self.buf[start..end], data);
+        self.initialized = cmp::max(self.initialized, end);
+    }
 
-        // SAFETY: We just added the entire contents of buf to the filled section.
-        unsafe {
-            self.set_init(buf.len());
+    /// Retains only the filled bytes for which `f` returns `true`, moving them to the front of the
+    /// filled part of the buffer in their original order.
+    ///
+    /// The filled length becomes the number of bytes retained; the initialized length does not
+    /// change.
+    #[inline]
+    pub fn retain_filled<F: FnMut(u8) -> bool>(&mut self, mut f: F) {
+        let filled = self.filled_mut();
+        let mut kept = 0;
+        for i in 0..filled.len() {
+            let b = filled[i];
+            if f(b) {
+                filled[kept] = b;
+                kept += 1;
+            }
         }
-        self.buf.filled += buf.len();
+
+        self.filled = self.head + kept;
+        self.consumed = cmp::min(self.consumed, kept);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::{self, Read};
+    /// Copies the bytes in `src` to the end of the filled part of the buffer.
+    ///
+    /// If `advancing` is `true`, the copied bytes become part of the filled region, otherwise they
+    /// are only marked as initialized.
+    ///
+    /// `src` must start within the filled region but may extend past its end. In that case the
+    /// source and destination overlap and bytes are copied one at a time from front to back, so
+    /// bytes copied earlier are repeated; this is the match copy of LZ77-style decompressors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` is not a valid range starting in the filled region, or if there is not
+    /// enough unfilled space for `src.len()` bytes.
+    #[inline]
+    #[track_caller]
+    pub fn copy_within_filled(&mut self, src: Range<usize>, advancing: bool) {
+        assert!(
+            src.start <= src.end,
+            "range starts at {} but ends at {}",
+            src.start,
+            src.end
+        );
+        let len = src.end - src.start;
+        assert!(
+            len == 0 || src.start < self.len(),
+            "range start index {} out of range for filled region of length {}",
+            src.start,
+            self.len()
+        );
+        assert!(
+            len <= self.remaining_capacity(),
+            "not enough capacity to copy {} bytes, only {} available",
+            len,
+            self.remaining_capacity()
+        );
 
-    fn read<'a, 'b>(mut buf: BorrowCursor<'a, 'b>) -> Result<(), ()> {
-        unsafe {
-            let raw_buf = buf.as_mut();
-            raw_buf[0].write(0);
-            raw_buf[1].write(1);
-            raw_buf[2].write(2);
-            raw_buf[3].write(3);
-            buf.advance(4);
+        let src = self.head + src.start..self.head + src.end;
+        let dst = self.filled;
+        if src.end <= dst {
+            self.buf.copy_within(src, dst);
+        } else {
+            for i in 0..len {
+                self.buf[dst + i] = self.buf[src.start + i];
+            }
+        }
+
+        self.initialized = cmp::max(self.initialized, dst + len);
+        if advancing {
+            self.filled += len;
         }
-        Ok(())
     }
 
-    fn read_buf<'a, 'b, R: Read + ?Sized>(
-        reader: &mut R,
-        mut buf: BorrowCursor<'a, 'b>,
-    ) -> io::Result<()> {
-        let p = buf.plone();
-        read(p).unwrap();
-        read(buf).unwrap();
-        Ok(())
+    /// Returns the length of the filled part of the buffer.
+    // A single load, used in read loop conditions.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub const fn len(&self) -> usize {
+        self.filled - self.head
     }
 
-    #[test]
-    fn it_works() {
-        let mut backing = Vec::with_capacity(32);
-        let mut buf: BorrowBuf = backing.spare_capacity_mut().into();
+    /// Returns the length of the initialized part of the buffer.
+    // A single load, used to decide whether to initialize before a read.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub const fn init_len(&self) -> usize {
+        self.initialized - self.head
+    }
 
-        read(buf.unfilled()).unwrap();
+    /// Returns the number of bytes which can still be filled.
+    ///
+    /// This is the same as the capacity of a cursor returned by `unfilled`.
+    #[inline]
+    pub const fn remaining_capacity(&self) -> usize {
+        self.buf.len() - self.filled
+    }
 
-        let len = buf.len();
+    /// Returns `true` if there is space left to fill in the buffer.
+    #[inline]
+    pub const fn has_remaining(&self) -> bool {
+        self.remaining_capacity() > 0
+    }
+
+    /// Appends `data` to the filled part of the buffer.
+    ///
+    /// This is a shorthand for `buf.unfilled().append(data)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.remaining_capacity()` is less than `data.len()`.
+    #[inline]
+    #[track_caller]
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.unfilled().append(data);
+    }
+
+    /// Appends `data` to the filled part of the buffer if there is space for all of it.
+    ///
+    /// If there is not enough space, nothing is written and an error is returned.
+    #[inline]
+    pub fn try_extend_from_slice(&mut self, data: &[u8]) -> Result<(), CapacityError> {
+        self.unfilled().try_append(data)
+    }
+
+    /// Appends the filled part of `other` to the filled part of this buffer.
+    ///
+    /// Returns `false` without writing anything if there is not enough space.
+    #[inline]
+    pub fn append_filled_of(&mut self, other: &BorrowBuf<'_>) -> bool {
+        self.try_extend_from_slice(other.filled()).is_ok()
+    }
+
+    /// Replaces the backing memory of the buffer with `new`, returning the new, empty buffer and
+    /// the filled part of the old one.
+    ///
+    /// The filled bytes are not copied; they are returned as a slice of the old backing memory
+    /// (which is no longer borrowed by a `BorrowBuf`), so they can be processed or copied into the
+    /// new buffer while it is being filled. Whether the old memory was initialized beyond the filled
+    /// part is forgotten.
+    #[inline]
+    pub fn replace_backing<'b>(
+        self,
+        new: &'b mut [MaybeUninit<u8>],
+    ) -> (BorrowBuf<'b>, &'a mut [u8]) {
+        let filled = &mut self.buf[self.head..self.filled];
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        let filled = unsafe { MaybeUninit::slice_assume_init_mut(filled) };
+        (new.into(), filled)
+    }
+
+    /// Returns the unfilled part of the buffer, starting at an address aligned to `align`.
+    ///
+    /// The bytes between the end of the filled part and the aligned address are zeroed and become
+    /// part of the filled region, so that data written to the returned slice and then filled
+    /// starts at an aligned address. If the buffer does not have enough space for the padding, the
+    /// whole buffer is filled and the returned slice is empty.
+    ///
+    /// The returned slice may start with bytes which are already initialized (see `init_len`).
+    ///
+    /// # Safety
+    ///
+    /// The caller must not write uninitialized bytes into the part of the returned slice which is
+    /// already initialized, that is, before `self.init_len()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    #[inline]
+    #[track_caller]
+    pub unsafe fn aligned_spare_mut(&mut self, align: usize) -> &mut [MaybeUninit<u8>] {
+        assert!(align.is_power_of_two(), "align must be a power of two");
+
+        let spare = &mut self.buf[self.filled..];
+        let pad = cmp::min(spare.as_ptr().align_offset(align), spare.len());
+        mem::zero(&mut spare[..pad]);
+
+        self.filled += pad;
+        self.initialized = cmp::max(self.initialized, self.filled);
+        &mut self.buf[self.filled..]
+    }
+
+    /// Fills the whole unfilled part of the buffer with random bytes from `rng`.
+    ///
+    /// Afterwards, the buffer is full.
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn fill_random<R: rand::RngCore + ?Sized>(&mut self, rng: &mut R) {
+        let mut cursor = self.unfilled();
+        rng.fill_bytes(cursor.ensure_init_mut());
+        let n = cursor.capacity();
         unsafe {
-            backing.set_len(len);
+            // SAFETY: the whole cursor was initialized above
+            cursor.advance(n);
         }
+    }
 
-        assert_eq!(backing.len(), 4);
-        assert_eq!(backing[0], 0);
-        assert_eq!(backing[1], 1);
-        assert_eq!(backing[2], 2);
-        assert_eq!(backing[3], 3);
+    /// Returns the base pointer and bookkeeping of the buffer in one go.
+    ///
+    /// This is intended for filling in C descriptors which describe a buffer by its start, used
+    /// length, and size. The pointer is only valid while the `BorrowBuf` is borrowed mutably and
+    /// is invalidated by any other use of the buffer; the counts are a snapshot and are not updated
+    /// if the buffer changes.
+    #[inline]
+    pub fn parts(&mut self) -> BufParts {
+        BufParts {
+            ptr: self.buf[self.head..].as_mut_ptr() as *mut u8,
+            filled: self.len(),
+            initialized: self.init_len(),
+            capacity: self.capacity(),
+        }
+    }
+
+    /// Copies filled bytes which have not yet been consumed into `dst`, consuming them.
+    ///
+    /// Copies as many bytes as fit in `dst` and returns the number copied. This is the same as
+    /// reading from the buffer with `Read::read`, and shares its read position.
+    #[inline]
+    pub fn drain_into(&mut self, dst: &mut [u8]) -> usize {
+        let unread = &self.filled()[self.consumed..];
+        let n = cmp::min(dst.len(), unread.len());
+        dst[..n].copy_from_slice(&unread[..n]);
+        self.consumed += n;
+        n
+    }
+
+    /// Returns the number of filled bytes which have not yet been consumed via `Read` or `BufRead`.
+    #[inline]
+    pub const fn unread_len(&self) -> usize {
+        self.len() - self.consumed
+    }
+
+    /// Moves filled bytes which have not yet been consumed into the unfilled part of `dst`.
+    ///
+    /// Copies as many bytes as fit in `dst`, consuming them from this buffer and filling them in `dst`, and returns
+    /// the number copied.
+    #[inline]
+    pub fn pipe_into(&mut self, dst: &mut BorrowBuf<'_>) -> usize {
+        let n = cmp::min(dst.remaining_capacity(), self.unread_len());
+        dst.extend_from_slice(&self.filled()[self.consumed..self.consumed + n]);
+        self.consumed += n;
+        n
+    }
+
+    /// Saves the filled length and `Read` position of the buffer, to go back to with `restore`.
+    ///
+    /// Unlike `snapshot`, the contents of the buffer are not saved, so this is only useful for
+    /// backtracking if the bytes filled before the checkpoint are not changed.
+    #[inline]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            filled: self.filled,
+            consumed: self.consumed,
+        }
+    }
+
+    /// Resets the filled length and `Read` position of the buffer to a checkpoint.
+    ///
+    /// The initialized part of the buffer is not changed. The checkpoint must have been taken from
+    /// this buffer, and is meaningless once the bytes it covers have been moved or rewritten, e.g.,
+    /// by `retain_filled`, `prepend`, or clearing and refilling the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checkpoint's filled length is not within the initialized part of the buffer,
+    /// which means it cannot have been taken from this buffer.
+    #[inline]
+    #[track_caller]
+    pub fn restore(&mut self, cp: Checkpoint) {
+        assert!(
+            self.head <= cp.filled && cp.filled <= self.initialized,
+            "checkpoint is not valid for this buffer"
+        );
+
+        self.filled = cp.filled;
+        self.consumed = cmp::min(cp.consumed, self.len());
+    }
+
+    /// Returns an owned copy of the initialized part of the buffer and its filled and initialized
+    /// lengths.
+    ///
+    /// The snapshot can later be restored with [`BufSnapshot::restore_into`], for example to roll
+    /// back a parser after a failed attempt.
+    pub fn snapshot(&self) -> BufSnapshot {
+        BufSnapshot {
+            data: self.initialized().to_vec(),
+            filled: self.len(),
+        }
+    }
+
+    /// Returns a guard over the next `N` unfilled bytes, for filling a fixed-size record.
+    ///
+    /// Any uninitialized bytes of the record are zeroed first, and the guard dereferences to the
+    /// record as an array. Calling [`RecordGuard::commit`] fills the record; dropping the guard
+    /// without committing leaves the filled part of the buffer unchanged. Returns `None` if fewer
+    /// than `N` bytes are unfilled.
+    #[inline]
+    pub fn record_mut<const N: usize>(&mut self) -> Option<RecordGuard<'_, N>> {
+        let end = self
+            .filled
+            .checked_add(N)
+            .filter(|&end| end <= self.buf.len())?;
+        if end > self.initialized {
+            mem::zero(&mut self.buf[self.initialized..end]);
+            self.initialized = end;
+        }
+
+        let record = &mut self.buf[self.filled..end];
+        // SAFETY: the record was initialized above
+        let record = unsafe { MaybeUninit::slice_assume_init_mut(record) };
+        Some(RecordGuard {
+            record: record.try_into().unwrap(),
+            filled: &mut self.filled,
+        })
+    }
+
+    /// Returns a wrapper whose `Debug` implementation does not show the contents of the buffer.
+    ///
+    /// Use this when logging buffers which may hold secrets.
+    #[inline]
+    pub fn redacted(&self) -> Redacted<'_, 'a> {
+        Redacted { buf: self }
+    }
+
+    /// Returns a cursor over the unfilled part of the buffer.
+    // Constructs the cursor once per read; inlining lets the cursor's fields live in registers.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub fn unfilled<'b>(&'b mut self) -> BorrowCursor<'a, 'b> {
+        BorrowCursor {
+            start: self.filled,
+            pos: self.filled,
+            end: self.buf.len(),
+            buf: self,
+        }
+    }
+
+    /// Calls `f` with the whole unfilled part of the buffer as `&mut [u8]`, then fills as many bytes as `f` returns.
+    ///
+    /// The unfilled part is initialized first. This adapts functions which write into a `&mut [u8]` and return the
+    /// number of bytes written, such as many encoders.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` returns more than the number of unfilled bytes.
+    #[inline]
+    #[track_caller]
+    pub fn fill_via<F: FnOnce(&mut [u8]) -> usize>(&mut self, f: F) {
+        let mut cursor = self.unfilled();
+        let n = f(cursor.ensure_init_mut());
+        assert!(
+            n <= cursor.capacity(),
+            "cannot fill {} bytes, only {} available",
+            n,
+            cursor.capacity()
+        );
+        // SAFETY: the whole cursor was initialized above
+        unsafe {
+            cursor.advance(n);
+        }
+    }
+
+    /// Calls `f` with a cursor over the unfilled part of the buffer until the buffer is full or `f` returns `Ok(0)`.
+    ///
+    /// `f` does the filling itself, e.g., by calling `read_buf`, and returns the number of bytes it filled; `Ok(0)`
+    /// means the end of the input. An error from `f` is returned at once, and the bytes filled before it stay filled.
+    /// Returns the total number of bytes filled.
+    #[inline]
+    pub fn fill_loop<E>(
+        &mut self,
+        mut f: impl FnMut(BorrowCursor<'a, '_>) -> Result<usize, E>,
+    ) -> Result<usize, E> {
+        let start = self.filled;
+        while self.has_remaining() {
+            if f(self.unfilled())? == 0 {
+                break;
+            }
+        }
+        Ok(self.filled - start)
+    }
+
+    /// Clears the buffer, resetting the filled region to empty.
+    ///
+    /// The number of initialized bytes is not changed, and the contents of the buffer are not modified.
+    /// Any bytes consumed via `Read` or `BufRead` are forgotten. Bytes which were prepended stay
+    /// part of the buffer's (now empty) space and do not become headroom again.
+    #[inline]
+    pub fn clear(&mut self) -> &mut Self {
+        self.filled = self.head;
+        self.consumed = 0;
+        self
+    }
+
+    /// Calls `f` with the filled portion of the buffer, then clears the buffer.
+    ///
+    /// This is for pipeline stages which process the filled bytes in place and then hand the buffer back for the next
+    /// read. As for `clear`, the initialized bytes stay initialized, so the next read need not initialize them again.
+    /// Returns the result of `f`.
+    #[inline]
+    pub fn process_and_clear<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        let result = f(self.filled_mut());
+        self.clear();
+        result
+    }
+
+    /// Asserts that the first `n` bytes of the buffer are initialized.
+    ///
+    /// `BorrowBuf` assumes that bytes are never de-initialized, so this method does nothing when called with fewer
+    /// bytes than are already known to be initialized.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `n` unfilled bytes of the buffer have already been initialized.
+    #[inline]
+    pub unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        debug_assert!(n <= self.capacity(), "set_init past the end of the buffer");
+        self.initialized = cmp::max(self.initialized, self.head + n);
+        self
+    }
+}
+
+/// Concatenates the filled parts of `bufs` into a new `Vec`.
+pub fn concat_filled(bufs: &[&BorrowBuf<'_>]) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(bufs.iter().map(|buf| buf.len()).sum());
+    for buf in bufs {
+        vec.extend_from_slice(buf.filled());
+    }
+    vec
+}
+
+/// Reads the filled bytes of the buffer which have not yet been consumed.
+impl<'a> io::Read for BorrowBuf<'a> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.drain_into(buf))
+    }
+}
+
+/// The filled part of the buffer is used as the buffer for `BufRead`.
+///
+/// Consuming bytes does not change the filled part of the buffer, it only moves the read position
+/// within it; `clear` resets the read position.
+impl<'a> io::BufRead for BorrowBuf<'a> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.filled()[self.consumed..])
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.consumed = cmp::min(self.consumed + amt, self.len());
+    }
+}
+
+/// Buffers are equal if their filled parts are equal; unfilled bytes and the initialized length are
+/// ignored.
+impl PartialEq for BorrowBuf<'_> {
+    #[inline]
+    fn eq(&self, other: &BorrowBuf<'_>) -> bool {
+        self.filled() == other.filled()
+    }
+}
+
+impl Eq for BorrowBuf<'_> {}
+
+/// Buffers are ordered by their filled parts, lexicographically, the same as `<[u8]>::cmp`. Bytes which are
+/// initialized but not filled do not take part.
+impl PartialOrd for BorrowBuf<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &BorrowBuf<'_>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BorrowBuf<'_> {
+    #[inline]
+    fn cmp(&self, other: &BorrowBuf<'_>) -> cmp::Ordering {
+        self.filled().cmp(other.filled())
+    }
+}
+
+impl PartialEq<[u8]> for BorrowBuf<'_> {
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        self.filled() == other
+    }
+}
+
+/// Only the filled part of the buffer is hashed, the same as hashing `self.filled()`. Like slices,
+/// the length is hashed too, so `[1, 2]` and `[12]` hash differently.
+impl Hash for BorrowBuf<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.filled().hash(state);
+    }
+}
+
+impl AsRef<[u8]> for BorrowBuf<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.filled()
+    }
+}
+
+/// Borrows the filled part of the buffer, consistent with the `Eq` and `Hash` implementations, so
+/// a collection of `BorrowBuf`s can be searched by byte slice.
+impl Borrow<[u8]> for BorrowBuf<'_> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.filled()
+    }
+}
+
+/// A view of a [`BorrowBuf`](BorrowBuf) which only shows its length when debug-printed, see
+/// [`BorrowBuf::redacted`].
+#[derive(Clone, Copy)]
+pub struct Redacted<'r, 'a> {
+    buf: &'r BorrowBuf<'a>,
+}
+
+impl fmt::Debug for Redacted<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BorrowBuf")
+            .field("filled", &self.buf.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A fixed-size record at the end of the filled part of a [`BorrowBuf`](BorrowBuf), see
+/// [`BorrowBuf::record_mut`].
+#[derive(Debug)]
+pub struct RecordGuard<'r, const N: usize> {
+    record: &'r mut [u8; N],
+    filled: &'r mut usize,
+}
+
+impl<const N: usize> RecordGuard<'_, N> {
+    /// Fills the record, growing the filled part of the buffer by `N` bytes.
+    #[inline]
+    pub fn commit(self) {
+        *self.filled += N;
+    }
+}
+
+impl<const N: usize> Deref for RecordGuard<'_, N> {
+    type Target = [u8; N];
+
+    #[inline]
+    fn deref(&self) -> &[u8; N] {
+        self.record
+    }
+}
+
+impl<const N: usize> DerefMut for RecordGuard<'_, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8; N] {
+        self.record
+    }
+}
+
+/// The raw parts of a [`BorrowBuf`](BorrowBuf), see [`BorrowBuf::parts`].
+///
+/// Since it contains a raw pointer, `BufParts` is neither `Send` nor `Sync`.
+#[derive(Debug, Clone, Copy)]
+pub struct BufParts {
+    /// Pointer to the start of the buffer.
+    pub ptr: *mut u8,
+    /// The length of the filled part of the buffer.
+    pub filled: usize,
+    /// The length of the initialized part of the buffer.
+    pub initialized: usize,
+    /// The total capacity of the buffer.
+    pub capacity: usize,
+}
+
+/// A saved position in a [`BorrowBuf`](BorrowBuf), see [`BorrowBuf::checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    filled: usize,
+    consumed: usize,
+}
+
+/// A saved fill position of a [`BorrowCursor`](BorrowCursor), see [`BorrowCursor::mark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteMark(usize);
+
+/// A copy of the state of a [`BorrowBuf`](BorrowBuf), see [`BorrowBuf::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufSnapshot {
+    // The initialized part of the buffer.
+    data: Vec<u8>,
+    filled: usize,
+}
+
+impl BufSnapshot {
+    /// Copies the snapshot into `buf`, replacing its contents and setting its filled and
+    /// initialized lengths to those of the snapshot.
+    ///
+    /// Any bytes of `buf` beyond the snapshot's initialized part keep their contents, and if they were
+    /// initialized they stay initialized. Bytes consumed via `Read` or `BufRead` are forgotten.
+    ///
+    /// Returns an error (and does not change `buf`) if the capacity of `buf` is smaller than the
+    /// snapshot's initialized length.
+    pub fn restore_into(&self, buf: &mut BorrowBuf<'_>) -> Result<(), CapacityError> {
+        if self.data.len() > buf.capacity() {
+            return Err(CapacityError {
+                requested: self.data.len(),
+                available: buf.capacity(),
+            });
+        }
+
+        let head = buf.head;
+        MaybeUninit::write_slice(&mut buf.buf[head..][..self.data.len()], &self.data);
+        buf.filled = head + self.filled;
+        buf.initialized = cmp::max(buf.initialized, head + self.data.len());
+        buf.consumed = 0;
+        Ok(())
+    }
+}
+
+/// The error returned when there is not enough space in a buffer for an operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CapacityError {
+    /// The number of bytes the operation needed.
+    pub requested: usize,
+    /// The number of bytes which were available.
+    pub available: usize,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not enough capacity: requested {} bytes, but only {} available",
+            self.requested, self.available
+        )
+    }
+}
+
+impl Error for CapacityError {}
+
+/// A cursor view of a [`BorrowBuf`](BorrowBuf).
+///
+/// Provides mutable access to the unfilled portion (both initialised and uninitialised data) from
+/// the buffer.
+///
+/// Like `BorrowBuf`, `BorrowCursor` is `Send` and `Sync`.
+#[derive(Debug)]
+pub struct BorrowCursor<'a, 'b> {
+    buf: &'b mut BorrowBuf<'a>,
+    // The length of the filled part of `buf` when the cursor was created.
+    start: usize,
+    // The write position used by `poke`, relative to the start of `buf` (not the cursor).
+    pos: usize,
+    // The end of the cursor's view of `buf`, usually the end of `buf.buf` (see `take`).
+    end: usize,
+}
+
+impl<'a, 'b> BorrowCursor<'a, 'b> {
+    fn plone<'c>(&'c mut self) -> BorrowCursor<'a, 'c> {
+        BorrowCursor {
+            buf: self.buf,
+            start: self.start,
+            pos: self.pos,
+            end: self.end,
+        }
+    }
+
+    /// Returns the available space in the cursor.
+    // Called by `append` and by callers sizing each read.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    pub fn capacity(&self) -> usize {
+        self.end - self.buf.filled
+    }
+
+    /// Returns `true` if there is no space left in the cursor.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.capacity() == 0
+    }
+
+    /// Returns the number of bytes written to this cursor since it was created from a `BorrowBuf`.
+    #[inline]
+    pub fn written(&self) -> usize {
+        self.buf.filled - self.start
+    }
+
+    /// Returns the bytes written to this cursor since it was created from a `BorrowBuf`.
+    ///
+    /// These are the last `self.written()` bytes of the buffer's filled part; unlike
+    /// `BorrowBuf::filled`, bytes which were filled before the cursor was created are not included.
+    #[inline]
+    pub fn this_write(&self) -> &[u8] {
+        // SAFETY: bytes between `start` and `filled` are filled, so they are initialized
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf.buf[self.start..self.buf.filled]) }
+    }
+
+    /// Wraps the cursor so that bytes it initialized but did not fill are zeroed when it is dropped.
+    ///
+    /// See [`ZeroingCursor`].
+    #[cfg(feature = "zeroize")]
+    #[inline]
+    pub fn zeroing(self) -> ZeroingCursor<'a, 'b> {
+        ZeroingCursor::new(self)
+    }
+
+    /// Saves the current fill position, for measuring how much is written after it with `written_since`.
+    #[inline]
+    pub fn mark(&self) -> WriteMark {
+        WriteMark(self.buf.filled)
+    }
+
+    /// Returns the number of bytes written to this cursor since `mark` was taken.
+    ///
+    /// If the cursor has been rewound to before the mark, this is zero.
+    #[inline]
+    pub fn written_since(&self, mark: WriteMark) -> usize {
+        self.buf.filled.saturating_sub(mark.0)
+    }
+
+    /// Wraps the cursor so that filling more than `limit` bytes through it panics.
+    ///
+    /// Unlike `take`, the capacity of the cursor is not changed. See [`LimitedCursor`].
+    #[inline]
+    pub fn limited(self, limit: usize) -> LimitedCursor<'a, 'b> {
+        LimitedCursor::new(self, limit)
+    }
+
+    /// Limits the cursor to writing at most `n` bytes.
+    ///
+    /// The returned cursor views the same buffer, but its capacity is at most `n`; advancing it
+    /// advances the underlying buffer as usual.
+    #[inline]
+    pub fn take(self, n: usize) -> BorrowCursor<'a, 'b> {
+        BorrowCursor {
+            end: self.buf.filled + cmp::min(n, self.capacity()),
+            ..self
+        }
+    }
+
+    // The end of the initialized part of the cursor.
+    #[inline]
+    fn init_end(&self) -> usize {
+        cmp::min(self.buf.initialized, self.end)
+    }
+
+    /// Returns a shared reference to the initialized portion of the buffer.
+    #[inline]
+    pub fn init_ref(&self) -> &[u8] {
+        //SAFETY: We only slice the initialized part of the buffer, which is always valid
+        unsafe {
+            MaybeUninit::slice_assume_init_ref(&self.buf.buf[self.buf.filled..self.init_end()])
+        }
+    }
+
+    /// Returns a mutable reference to the initialized portion of the buffer.
+    #[inline]
+    pub fn init_mut(&mut self) -> &mut [u8] {
+        //SAFETY: We only slice the initialized part of the buffer, which is always valid
+        unsafe {
+            let init_end = self.init_end();
+            MaybeUninit::slice_assume_init_mut(&mut self.buf.buf[self.buf.filled..init_end])
+        }
+    }
+
+    /// Returns a mutable reference to the uninitialized part of the buffer.
+    ///
+    /// It is safe to uninitialize any of these bytes.
+    #[inline]
+    pub fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let init_end = self.init_end();
+        &mut self.buf.buf[init_end..self.end]
+    }
+
+    /// Returns the number of initialized bytes in the cursor, without forming a slice.
+    ///
+    /// This is the same as `self.init_ref().len()`. Reads into up to this many bytes do not need
+    /// `ensure_init`.
+    #[inline]
+    pub fn remaining_init_len(&self) -> usize {
+        self.init_end() - self.buf.filled
+    }
+
+    /// Returns the lengths of the initialized and uninitialized parts of the cursor.
+    ///
+    /// The lengths always add up to `self.capacity()`.
+    #[inline]
+    pub fn region_lens(&self) -> (usize, usize) {
+        let init = self.remaining_init_len();
+        (init, self.capacity() - init)
+    }
+
+    /// Returns the first `N` uninitialized bytes of the cursor as an array.
+    ///
+    /// This is for APIs which take a fixed-size block, such as block ciphers. Returns `None` if
+    /// fewer than `N` bytes of the cursor are uninitialized.
+    #[inline]
+    pub fn uninit_array_mut<const N: usize>(&mut self) -> Option<&mut [MaybeUninit<u8>; N]> {
+        self.uninit_mut().get_mut(..N)?.try_into().ok()
+    }
+
+    /// Splits the uninitialized part of the cursor into a header of `H` bytes and the rest.
+    ///
+    /// This is like `slice::split_first_chunk_mut` over `uninit_mut`, for filling a fixed-size header and a body in
+    /// one borrow. Returns `None` if fewer than `H` bytes of the cursor are uninitialized.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub fn split_header_mut<const H: usize>(
+        &mut self,
+    ) -> Option<(&mut [MaybeUninit<u8>; H], &mut [MaybeUninit<u8>])> {
+        self.uninit_mut().split_first_chunk_mut::<H>()
+    }
+
+    /// A view of the cursor as a mutable slice of `MaybeUninit<u8>`.
+    #[inline]
+    pub unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        &mut self.buf.buf[self.buf.filled..self.end]
+    }
+
+    /// Advances the cursor by the number of bytes returned from a read into `init_mut`.
+    ///
+    /// On `Ok(n)`, the cursor is advanced by `n` and `Ok(n)` is returned. An error is returned unchanged and the
+    /// cursor is not touched. Since the bytes must already be initialized for this to be safe, this is for reads into
+    /// the initialized part of the cursor (e.g., after `ensure_init`); a read into uninitialized memory by other means
+    /// must use `set_init` and `advance` instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the number of initialized bytes in the cursor.
+    #[inline]
+    #[track_caller]
+    pub fn commit_read(&mut self, result: io::Result<usize>) -> io::Result<usize> {
+        let n = result?;
+        assert!(
+            n <= self.remaining_init_len(),
+            "read of {} bytes exceeds the {} initialized bytes of the cursor",
+            n,
+            self.remaining_init_len()
+        );
+        // SAFETY: the first `n` bytes of the cursor are initialized, checked above
+        unsafe {
+            self.advance(n);
+        }
+        Ok(n)
+    }
+
+    /// Increases the size of the filled region of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `n` elements of the cursor have been properly
+    /// initialised.
+    // Called after every read; two adds and a max, which should fold into the caller.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    #[track_caller]
+    pub unsafe fn advance(&mut self, n: usize) -> &mut Self {
+        // Advancing past the end is already undefined behaviour, so only check in debug builds to
+        // keep this cheap; it also rules out `filled` overflowing.
+        debug_assert!(n <= self.capacity(), "advance past the end of the cursor");
+        self.buf.filled += n;
+        self.buf.initialized = cmp::max(self.buf.initialized, self.buf.filled);
+        self
+    }
+
+    /// Shrinks the filled region of the buffer by `n` bytes, giving them back to the cursor.
+    ///
+    /// This is the inverse of `advance`, but only bytes which were filled via this cursor can be
+    /// given back. The bytes remain initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than `self.written()`.
+    #[inline]
+    #[track_caller]
+    pub fn rewind(&mut self, n: usize) -> &mut Self {
+        assert!(
+            n <= self.written(),
+            "cannot rewind {} bytes, only {} were written by this cursor",
+            n,
+            self.written()
+        );
+        self.buf.filled -= n;
+        self.buf.consumed = cmp::min(self.buf.consumed, self.buf.len());
+        self
+    }
+
+    /// Initialised all bytes in the cursor.
+    #[inline]
+    pub fn ensure_init(&mut self) -> &mut Self {
+        mem::zero(self.uninit_mut());
+        self.buf.initialized = cmp::max(self.buf.initialized, self.end);
+
+        self
+    }
+
+    /// Initializes all bytes in the cursor and returns them as one slice.
+    ///
+    /// The returned slice always has length `capacity()`, which makes it suitable for passing to APIs which only
+    /// accept `&mut [u8]`. As for `ensure_init`, only the uninitialized bytes are zeroed; bytes initialized by an
+    /// earlier read keep whatever was left there, so this is cheap when most of the cursor is initialized.
+    #[inline]
+    pub fn ensure_init_mut(&mut self) -> &mut [u8] {
+        self.ensure_init().init_mut()
+    }
+
+    /// Initializes all bytes in the cursor and splits them into `IoSliceMut`s of `chunk` bytes, for a vectored read.
+    ///
+    /// The last slice is shorter if the capacity is not a multiple of `chunk`. After the read, advance the cursor
+    /// once by the total number of bytes read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is zero.
+    #[inline]
+    #[track_caller]
+    pub fn as_io_slices_mut(&mut self, chunk: usize) -> Vec<io::IoSliceMut<'_>> {
+        assert!(chunk > 0, "chunk size must be non-zero");
+        self.ensure_init_mut()
+            .chunks_mut(chunk)
+            .map(io::IoSliceMut::new)
+            .collect()
+    }
+
+    /// Initializes the first `N` uninitialized bytes of the cursor.
+    ///
+    /// Unlike `ensure_init`, the number of bytes written is known at compile time, so for small `N` (e.g., a length
+    /// prefix) the compiler can emit a few stores instead of a call to `memset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `N` bytes of the cursor are uninitialized.
+    #[inline]
+    #[track_caller]
+    pub fn ensure_init_n<const N: usize>(&mut self) -> &mut Self {
+        let start = self.init_end();
+        assert!(
+            N <= self.end - start,
+            "cannot initialize {} bytes, only {} are uninitialized",
+            N,
+            self.end - start
+        );
+
+        let dst: &mut [MaybeUninit<u8>; N] = (&mut self.uninit_mut()[..N]).try_into().unwrap();
+        *dst = [MaybeUninit::new(0); N];
+        self.buf.initialized = cmp::max(self.buf.initialized, start + N);
+
+        self
+    }
+
+    /// Asserts that the first `n` unfilled bytes of the cursor are initialized.
+    ///
+    /// `BorrowBuf` assumes that bytes are never de-initialized, so this method does nothing when called with fewer
+    /// bytes than are already known to be initialized.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `n` bytes of the buffer have already been initialized.
+    #[inline]
+    pub unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        debug_assert!(n <= self.capacity(), "set_init past the end of the cursor");
+        self.buf.initialized = cmp::max(self.buf.initialized, self.buf.filled + n);
+        self
+    }
+
+    /// Appends data to the cursor, advancing the position within its buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.capacity()` is less than `buf.len()`.
+    // When `buf.len()` is known at the call site, inlining reduces the copy to a few stores.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    #[track_caller]
+    pub fn append(&mut self, buf: &[u8]) {
+        assert!(self.capacity() >= buf.len());
+
+        // When reusing a buffer the destination is often already initialized, in which case we
+        // can copy into it as plain bytes and skip updating the initialized count.
+        if buf.len() <= self.init_end() - self.buf.filled {
+            self.init_mut()[..buf.len()].copy_from_slice(buf);
+            self.buf.filled += buf.len();
+            return;
+        }
+
+        // SAFETY: we do not de-initialize any of the elements of the slice
+        unsafe {
+            mem::copy(self.as_mut(), buf);
+        }
+
+        // SAFETY: We just added the entire contents of buf to the filled section.
+        unsafe {
+            self.set_init(buf.len());
+        }
+        self.buf.filled += buf.len();
+    }
+
+    /// Returns the write position used by `poke`, relative to the start of the cursor.
+    ///
+    /// The position is independent of the filled region of the buffer; if the cursor is advanced
+    /// past the position, the position is at the start of the cursor.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos.saturating_sub(self.buf.filled)
+    }
+
+    /// Sets the write position used by `poke`, relative to the start of the cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than `self.capacity()`.
+    #[inline]
+    #[track_caller]
+    pub fn set_position(&mut self, n: usize) {
+        assert!(
+            n <= self.capacity(),
+            "cannot set the position to {}, only {} available",
+            n,
+            self.capacity()
+        );
+        self.pos = self.buf.filled + n;
+    }
+
+    /// Writes `bytes` at the current write position and moves the position past them.
+    ///
+    /// Unlike `append`, this does not grow the filled region of the buffer; the bytes only become
+    /// part of the filled region when the cursor is explicitly advanced over them. This allows,
+    /// for example, writing a placeholder length prefix, writing the body, and then going back to
+    /// overwrite the prefix before advancing.
+    ///
+    /// The written bytes are marked as initialized. If the write position is past the initialized
+    /// part of the cursor, the gap is zeroed so that the initialized region remains contiguous.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not enough space after the write position for `bytes`.
+    #[inline]
+    #[track_caller]
+    pub fn poke(&mut self, bytes: &[u8]) {
+        // The position may be past the end of a `take` cursor.
+        assert!(
+            self.position() <= self.capacity() && bytes.len() <= self.capacity() - self.position(),
+            "cannot poke {} bytes at position {}, only {} available",
+            bytes.len(),
+            self.position(),
+            self.capacity()
+        );
+        let start = self.buf.filled + self.position();
+        let end = start + bytes.len();
+
+        if start > self.buf.initialized {
+            mem::zero(&mut self.buf.buf[self.buf.initialized..start]);
+        }
+        MaybeUninit::write_slice(&mut self.buf.buf[start..end], bytes);
+
+        self.buf.initialized = cmp::max(self.buf.initialized, end);
+        self.pos = end;
+    }
+
+    /// Appends a single byte to the cursor, advancing the position within its buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is full.
+    // Called in byte-at-a-time encoder loops, where a call per byte dominates.
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    #[cfg_attr(feature = "no-inline", inline)]
+    #[track_caller]
+    pub fn append_byte(&mut self, b: u8) {
+        assert!(self.capacity() >= 1);
+
+        self.buf.buf[self.buf.filled].write(b);
+        self.buf.filled += 1;
+        self.buf.initialized = cmp::max(self.buf.initialized, self.buf.filled);
+    }
+
+    /// Fills the whole cursor with repetitions of `pattern`, advancing to the end of the cursor.
+    ///
+    /// If the capacity of the cursor is not a multiple of `pattern.len()`, the last repetition is
+    /// truncated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is empty.
+    #[inline]
+    #[track_caller]
+    pub fn fill_repeating(&mut self, pattern: &[u8]) {
+        assert!(!pattern.is_empty(), "cannot fill with an empty pattern");
+
+        let n = self.capacity();
+        for chunk in self.buf.buf[self.buf.filled..self.end].chunks_mut(pattern.len()) {
+            MaybeUninit::write_slice(chunk, &pattern[..chunk.len()]);
+        }
+        unsafe {
+            // SAFETY: we just wrote n bytes
+            self.advance(n);
+        }
+    }
+
+    /// Appends a single byte to the cursor if it is not full.
+    ///
+    /// Returns `false` without writing anything if there is no space left.
+    #[inline]
+    pub fn try_append_byte(&mut self, b: u8) -> bool {
+        if self.capacity() == 0 {
+            return false;
+        }
+
+        self.append_byte(b);
+        true
+    }
+
+    /// Appends `c` encoded as UTF-8 to the cursor if there is space for all of its bytes.
+    ///
+    /// Returns `false` without writing anything if there is not enough space.
+    #[inline]
+    pub fn append_char(&mut self, c: char) -> bool {
+        let mut bytes = [0; 4];
+        self.try_append(c.encode_utf8(&mut bytes).as_bytes())
+            .is_ok()
+    }
+
+    /// Appends data to the cursor if there is space for all of it.
+    ///
+    /// If `self.capacity()` is less than `buf.len()`, nothing is written and an error is returned.
+    #[inline]
+    pub fn try_append(&mut self, buf: &[u8]) -> Result<(), CapacityError> {
+        if buf.len() > self.capacity() {
+            return Err(CapacityError {
+                requested: buf.len(),
+                available: self.capacity(),
+            });
+        }
+
+        self.append(buf);
+        Ok(())
+    }
+
+    /// Appends several slices to the cursor, one after another.
+    ///
+    /// Either all of `bufs` is appended or, if there is not enough space for all of them, nothing
+    /// is written and an error is returned.
+    #[inline]
+    pub fn append_all(&mut self, bufs: &[&[u8]]) -> Result<(), CapacityError> {
+        // The slices may alias, so their total length can overflow.
+        let total = bufs
+            .iter()
+            .fold(0usize, |total, b| total.saturating_add(b.len()));
+        if total > self.capacity() {
+            return Err(CapacityError {
+                requested: total,
+                available: self.capacity(),
+            });
+        }
+
+        for buf in bufs {
+            self.append(buf);
+        }
+        Ok(())
+    }
+
+    /// Appends the contents of several `IoSlice`s to the cursor, one after another, as for a
+    /// gather write.
+    ///
+    /// Either all of `slices` is appended or, if there is not enough space for all of them, nothing
+    /// is written and an error is returned. Returns the number of bytes appended.
+    #[inline]
+    pub fn append_io_slices(&mut self, slices: &[io::IoSlice<'_>]) -> Result<usize, CapacityError> {
+        let total = slices
+            .iter()
+            .fold(0usize, |total, s| total.saturating_add(s.len()));
+        if total > self.capacity() {
+            return Err(CapacityError {
+                requested: total,
+                available: self.capacity(),
+            });
+        }
+
+        for slice in slices {
+            self.append(slice);
+        }
+        Ok(total)
+    }
+
+    /// Appends `parts` to the cursor with `sep` between each pair of them, e.g., for newline-delimited records.
+    ///
+    /// Either everything is appended or, if there is not enough space for all of it, nothing is written and an error
+    /// is returned. Returns the number of bytes appended.
+    #[inline]
+    pub fn append_joined(&mut self, parts: &[&[u8]], sep: &[u8]) -> Result<usize, CapacityError> {
+        let seps = parts.len().saturating_sub(1).saturating_mul(sep.len());
+        let total = parts
+            .iter()
+            .fold(seps, |total, part| total.saturating_add(part.len()));
+        if total > self.capacity() {
+            return Err(CapacityError {
+                requested: total,
+                available: self.capacity(),
+            });
+        }
+
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                self.append(sep);
+            }
+            self.append(part);
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Read};
+
+    fn read<'a, 'b>(mut buf: BorrowCursor<'a, 'b>) -> Result<(), ()> {
+        unsafe {
+            let raw_buf = buf.as_mut();
+            raw_buf[0].write(0);
+            raw_buf[1].write(1);
+            raw_buf[2].write(2);
+            raw_buf[3].write(3);
+            buf.advance(4);
+        }
+        Ok(())
+    }
+
+    fn read_buf<'a, 'b, R: Read + ?Sized>(
+        reader: &mut R,
+        mut buf: BorrowCursor<'a, 'b>,
+    ) -> io::Result<()> {
+        let p = buf.plone();
+        read(p).unwrap();
+        read(buf).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn it_works() {
+        let mut backing = Vec::with_capacity(32);
+        let mut buf: BorrowBuf = backing.spare_capacity_mut().into();
+
+        read(buf.unfilled()).unwrap();
+
+        let len = buf.len();
+        unsafe {
+            backing.set_len(len);
+        }
+
+        assert_eq!(backing.len(), 4);
+        assert_eq!(backing[0], 0);
+        assert_eq!(backing[1], 1);
+        assert_eq!(backing[2], 2);
+        assert_eq!(backing[3], 3);
+    }
+
+    #[test]
+    fn remaining_capacity() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        assert_eq!(buf.remaining_capacity(), 8);
+        assert!(buf.has_remaining());
+
+        buf.unfilled().append(&[1; 8]);
+        assert_eq!(buf.remaining_capacity(), 0);
+        assert_eq!(buf.remaining_capacity(), buf.unfilled().capacity());
+        assert!(!buf.has_remaining());
+    }
+
+    #[test]
+    fn parts() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3]);
+        unsafe {
+            buf.set_init(5);
+        }
+
+        let parts = buf.parts();
+        assert_eq!(parts.filled, buf.len());
+        assert_eq!(parts.initialized, buf.init_len());
+        assert_eq!(parts.capacity, buf.capacity());
+        assert_eq!(parts.ptr as *const u8, buf.filled().as_ptr());
+    }
+
+    #[test]
+    fn append_all() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        buf.unfilled()
+            .append_all(&[&[1, 2], &[], &[3, 4, 5]])
+            .unwrap();
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            buf.unfilled().append_all(&[&[6, 7], &[8, 9]]),
+            Err(CapacityError {
+                requested: 4,
+                available: 3
+            })
+        );
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn append_byte() {
+        let mut backing = [MaybeUninit::uninit(); 2];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        cursor.append_byte(1);
+        assert!(cursor.try_append_byte(2));
+        assert!(!cursor.try_append_byte(3));
+        assert_eq!(buf.filled(), &[1, 2]);
+        assert_eq!(buf.init_len(), 2);
+    }
+
+    #[test]
+    fn poke_backpatch() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        cursor.poke(&[0; 4]);
+        cursor.poke(b"hello");
+        assert_eq!(cursor.position(), 9);
+        let len = cursor.position() as u32 - 4;
+
+        cursor.set_position(0);
+        cursor.poke(&len.to_be_bytes());
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.init_ref(), b"\0\0\0\x05hello");
+
+        unsafe {
+            cursor.advance(9);
+        }
+        assert_eq!(cursor.position(), 0);
+        assert_eq!(buf.filled(), b"\0\0\0\x05hello");
+    }
+
+    #[test]
+    fn poke_past_initialized() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        cursor.set_position(2);
+        cursor.poke(&[1, 2]);
+        assert_eq!(cursor.init_ref(), &[0, 0, 1, 2]);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.init_len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot poke 1 bytes at position 5, only 3 available")]
+    fn poke_past_take() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        cursor.set_position(5);
+        cursor.take(3).poke(&[1]);
+    }
+
+    #[test]
+    fn backpatch() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        buf.unfilled().append(&[0; 4]);
+        buf.unfilled().append(b"body");
+        let len = (buf.len() - 4) as u32;
+        buf.backpatch(0, &len.to_le_bytes());
+        assert_eq!(buf.filled(), b"\x04\0\0\0body");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range for filled region of length 4")]
+    fn backpatch_past_filled() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        buf.unfilled().append(&[0; 4]);
+        buf.backpatch(2, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn take() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled().take(4);
+        assert_eq!(cursor.capacity(), 4);
+        cursor.ensure_init();
+        assert_eq!(cursor.init_ref().len(), 4);
+        cursor.append(&[3, 4, 5]);
+        assert_eq!(cursor.capacity(), 1);
+        assert!(cursor.try_append(&[6, 7]).is_err());
+
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5]);
+        assert_eq!(buf.init_len(), 6);
+        assert_eq!(buf.unfilled().take(100).capacity(), 11);
+    }
+
+    #[test]
+    fn buf_read() {
+        use std::io::BufRead;
+
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"one\ntwo\nthr");
+
+        let mut line = Vec::new();
+        buf.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(line, b"one\n");
+        line.clear();
+        buf.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(line, b"two\n");
+        line.clear();
+        buf.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(line, b"thr");
+        assert_eq!(buf.fill_buf().unwrap(), b"");
+
+        buf.consume(100);
+        assert_eq!(buf.len(), 11);
+        buf.clear();
+        buf.unfilled().append(b"four");
+        assert_eq!(buf.fill_buf().unwrap(), b"four");
+    }
+
+    #[test]
+    fn copy_within_filled() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"abc");
+
+        buf.copy_within_filled(0..2, true);
+        assert_eq!(buf.filled(), b"abcab");
+
+        // Overlapping back-reference: distance 2, length 5.
+        buf.copy_within_filled(3..8, true);
+        assert_eq!(buf.filled(), b"abcabababa");
+
+        buf.copy_within_filled(0..3, false);
+        assert_eq!(buf.len(), 10);
+        assert_eq!(buf.init_len(), 13);
+        assert_eq!(buf.unfilled().init_ref(), b"abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough capacity to copy 3 bytes, only 2 available")]
+    fn copy_within_filled_past_capacity() {
+        let mut backing = [MaybeUninit::uninit(); 5];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"abc");
+        buf.copy_within_filled(0..3, true);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled();
+        cursor.append(&[3, 4, 5, 6]);
+        assert_eq!(cursor.written(), 4);
+        cursor.rewind(2);
+        assert_eq!(cursor.written(), 2);
+        assert_eq!(cursor.init_ref(), &[5, 6]);
+
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(buf.init_len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "only 1 were written by this cursor")]
+    fn rewind_past_cursor_start() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled();
+        cursor.append(&[3]);
+        cursor.rewind(2);
+    }
+
+    #[test]
+    fn initialized() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3, 4]);
+        buf.clear();
+        buf.unfilled().append(&[5]);
+
+        assert_eq!(buf.filled(), &[5]);
+        assert_eq!(buf.initialized(), &[5, 2, 3, 4]);
+    }
+
+    #[test]
+    fn try_with_min_capacity() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let buf = BorrowBuf::try_with_min_capacity(&mut backing[..], 8).unwrap();
+        assert_eq!(buf.capacity(), 8);
+
+        let err = BorrowBuf::try_with_min_capacity(&mut backing[..], 12).unwrap_err();
+        assert_eq!(
+            err,
+            CapacityError {
+                requested: 12,
+                available: 8
+            }
+        );
+    }
+
+    #[test]
+    fn is_full() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        while !cursor.is_full() {
+            cursor.append_byte(1);
+        }
+        assert_eq!(buf.filled(), &[1; 4]);
+    }
+
+    #[test]
+    fn extend_from_slice() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        buf.extend_from_slice(&[1, 2]);
+        buf.try_extend_from_slice(&[3]).unwrap();
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+
+        let err = buf.try_extend_from_slice(&[4, 5]).unwrap_err();
+        assert_eq!(err.available, 1);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn extend_from_slice_overflow() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn replace_backing() {
+        let mut front = [MaybeUninit::uninit(); 4];
+        let mut back = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut front[..]);
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        let (mut buf, old) = buf.replace_backing(&mut back[..]);
+        assert_eq!(old, &[1, 2, 3]);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.capacity(), 8);
+
+        buf.extend_from_slice(old);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn redacted() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+
+        let debug = format!("{:?}", buf.redacted());
+        assert_eq!(debug, "BorrowBuf { filled: 4, .. }");
+        assert!(!debug.contains("222") && !debug.contains("239"));
+    }
+
+    #[test]
+    fn aligned_spare_mut() {
+        #[repr(align(16))]
+        struct Aligned([MaybeUninit<u8>; 64]);
+
+        let mut backing = Aligned([MaybeUninit::uninit(); 64]);
+        let mut buf = BorrowBuf::from(&mut backing.0[..]);
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        // SAFETY: nothing is written to the spare bytes
+        let spare = unsafe { buf.aligned_spare_mut(16) };
+        assert_eq!(spare.as_ptr() as usize % 16, 0);
+        assert_eq!(spare.len(), 48);
+        assert_eq!(buf.len(), 16);
+        assert_eq!(&buf.filled()[3..], &[0; 13]);
+
+        // Already aligned, so no padding.
+        // SAFETY: nothing is written to the spare bytes
+        unsafe {
+            buf.aligned_spare_mut(8);
+        }
+        assert_eq!(buf.len(), 16);
+    }
+
+    #[test]
+    fn aligned_spare_mut_initialized() {
+        #[repr(align(16))]
+        struct Aligned([MaybeUninit<u8>; 64]);
+
+        let mut backing = Aligned([MaybeUninit::new(0xff); 64]);
+        let mut buf = BorrowBuf::from(&mut backing.0[..]);
+        // SAFETY: the whole backing memory was initialized above
+        unsafe {
+            buf.set_init(40);
+        }
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        // The spare slice overlaps the initialized bytes, which stay initialized.
+        // SAFETY: only initialized bytes are written
+        let spare = unsafe { buf.aligned_spare_mut(16) };
+        spare[0].write(7);
+        assert_eq!(buf.len(), 16);
+        assert_eq!(buf.init_len(), 40);
+        assert_eq!(buf.initialized()[16], 7);
+        assert_eq!(&buf.initialized()[17..], &[0xff; 23]);
+    }
+
+    #[test]
+    fn append_into_initialized() {
+        let mut cold_backing = [MaybeUninit::uninit(); 8];
+        let mut cold = BorrowBuf::from(&mut cold_backing[..]);
+        let mut warm_backing = [0xff; 8];
+        let mut warm = BorrowBuf::from(&mut warm_backing[..]);
+
+        for buf in [&mut cold, &mut warm] {
+            buf.unfilled().append(&[1, 2, 3]);
+            buf.unfilled().append(&[4, 5, 6, 7, 8]);
+        }
+        assert_eq!(cold.filled(), warm.filled());
+        assert_eq!(cold.init_len(), warm.init_len());
+    }
+
+    #[test]
+    fn from_filled_slice() {
+        let mut data = [1, 2, 3, 4];
+        let mut buf = BorrowBuf::from_filled_slice(&mut data[..]);
+        assert_eq!(buf.filled().len(), 4);
+        assert_eq!(buf.init_len(), 4);
+        assert!(!buf.has_remaining());
+
+        buf.backpatch(1, &[9]);
+        assert_eq!(buf.filled(), &[1, 9, 3, 4]);
+    }
+
+    #[test]
+    fn ensure_init_mut() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3]);
+
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init_n::<2>();
+        let capacity = cursor.capacity();
+        let slice = cursor.ensure_init_mut();
+        assert_eq!(slice.len(), capacity);
+        slice[0] = 7;
+        assert_eq!(buf.init_len(), 16);
+    }
+
+    #[test]
+    fn ensure_init_n() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init_n::<4>();
+        assert_eq!(cursor.init_ref(), &[0; 4]);
+        cursor.ensure_init_n::<2>();
+        assert_eq!(cursor.init_ref().len(), 6);
+        assert_eq!(buf.init_len(), 8);
+
+        // Bytes initialized past the end of a `take` cursor stay initialized.
+        buf.unfilled().ensure_init();
+        buf.unfilled().take(2).ensure_init_n::<0>();
+        assert_eq!(buf.init_len(), 16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ensure_init_n_too_many() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().ensure_init_n::<5>();
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3]);
+        buf.unfilled().take(4).ensure_init();
+        let snapshot = buf.snapshot();
+
+        buf.unfilled().append(&[9; 8]);
+        let mut larger = [MaybeUninit::uninit(); 32];
+        let mut larger = BorrowBuf::from(&mut larger[..]);
+        for buf in [&mut buf, &mut larger] {
+            snapshot.restore_into(buf).unwrap();
+            assert_eq!(buf.filled(), &[1, 2, 3]);
+            assert_eq!(&buf.initialized()[..7], &[1, 2, 3, 0, 0, 0, 0]);
+        }
+        assert_eq!(buf.init_len(), 11);
+        assert_eq!(larger.init_len(), 7);
+
+        let mut smaller = [MaybeUninit::uninit(); 4];
+        let mut smaller = BorrowBuf::from(&mut smaller[..]);
+        assert_eq!(
+            snapshot.restore_into(&mut smaller),
+            Err(CapacityError {
+                requested: 7,
+                available: 4
+            })
+        );
+        assert_eq!(smaller.len(), 0);
+    }
+
+    #[test]
+    fn fill_repeating() {
+        let mut backing = [MaybeUninit::uninit(); 12];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled();
+        cursor.fill_repeating(&[0xde, 0xad, 0xbe, 0xef]);
+        assert!(cursor.is_full());
+        assert_eq!(cursor.written(), 10);
+        assert_eq!(
+            buf.filled(),
+            &[1, 2, 0xde, 0xad, 0xbe, 0xef, 0xde, 0xad, 0xbe, 0xef, 0xde, 0xad]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn fill_repeating_empty_pattern() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().fill_repeating(&[]);
+    }
+
+    #[test]
+    fn hash_eq() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash(buf: &BorrowBuf<'_>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            buf.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut a = [MaybeUninit::uninit(); 8];
+        let mut a = BorrowBuf::from(&mut a[..]);
+        a.unfilled().append(&[1, 2, 3]);
+        let mut b = [0; 4];
+        let mut b = BorrowBuf::from(&mut b[..]);
+        b.unfilled().append(&[1, 2, 3]);
+
+        assert_eq!(a, b);
+        assert!(a == [1, 2, 3][..]);
+        assert_eq!(hash(&a), hash(&b));
+
+        b.unfilled().append(&[4]);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn uninit_array_mut() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init_n::<2>();
+
+        let block = cursor.uninit_array_mut::<4>().unwrap();
+        block[0].write(1);
+        assert!(cursor.uninit_array_mut::<6>().is_some());
+        assert!(cursor.uninit_array_mut::<7>().is_none());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn fill_random() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut a = [MaybeUninit::uninit(); 64];
+        let mut a = BorrowBuf::from(&mut a[..]);
+        a.unfilled().append(&[1, 2]);
+        a.fill_random(&mut StdRng::seed_from_u64(7));
+        assert_eq!(a.len(), a.capacity());
+        assert_eq!(a.init_len(), a.capacity());
+
+        let mut b = [0; 64];
+        let mut b = BorrowBuf::from(&mut b[..]);
+        b.unfilled().append(&[1, 2]);
+        b.fill_random(&mut StdRng::seed_from_u64(7));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn with_headroom() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::with_headroom(&mut backing[..], 4);
+        assert_eq!(buf.headroom(), 4);
+        assert_eq!(buf.capacity(), 12);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.init_len(), 0);
+
+        buf.unfilled().append(b"body");
+        assert_eq!(buf.filled(), b"body");
+        assert_eq!(buf.remaining_capacity(), 8);
+
+        buf.prepend(&4u32.to_be_bytes());
+        assert_eq!(buf.headroom(), 0);
+        assert_eq!(buf.capacity(), 16);
+        assert_eq!(buf.filled(), b"\0\0\0\x04body");
+        assert_eq!(buf.init_len(), 8);
+        assert_eq!(buf.remaining_capacity(), 8);
+        assert_eq!(buf.parts().ptr as *const u8, buf.filled().as_ptr());
+    }
+
+    #[test]
+    #[should_panic]
+    fn prepend_past_headroom() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::with_headroom(&mut backing[..], 2);
+        buf.prepend(&[1, 2, 3]);
+    }
+
+    #[test]
+    fn new_const() {
+        const CAPACITY: usize = {
+            let mut backing = [MaybeUninit::uninit(); 8];
+            let buf = BorrowBuf::new_const(&mut backing);
+            assert!(buf.len() == 0 && buf.has_remaining());
+            buf.capacity()
+        };
+        assert_eq!(CAPACITY, 8);
+    }
+
+    #[test]
+    fn remaining_init_len() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        assert_eq!(cursor.remaining_init_len(), 0);
+
+        // As if a reader zeroed 8 bytes but only filled 3 of them.
+        cursor.ensure_init_n::<8>();
+        unsafe {
+            cursor.set_init(8).advance(3);
+        }
+        assert_eq!(cursor.remaining_init_len(), 5);
+        assert_eq!(cursor.remaining_init_len(), cursor.init_ref().len());
+        assert_eq!(buf.unfilled().take(2).remaining_init_len(), 2);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "advance past the end of the cursor")]
+    fn advance_past_capacity() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        unsafe {
+            buf.unfilled().advance(usize::MAX);
+        }
+    }
+
+    #[test]
+    fn region_lens() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1; 3]);
+
+        let mut cursor = buf.unfilled();
+        assert_eq!(cursor.region_lens(), (0, 13));
+        cursor.ensure_init_n::<4>();
+        let (init, uninit) = cursor.region_lens();
+        assert_eq!((init, uninit), (4, 9));
+        assert_eq!(init + uninit, cursor.capacity());
+    }
+
+    #[test]
+    fn retain_filled() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"a\r\nb\r\n\r\n");
+
+        buf.retain_filled(|b| b != b'\r');
+        assert_eq!(buf.len(), 5);
+        assert_eq!(buf.filled(), b"a\nb\n\n");
+        assert_eq!(buf.init_len(), 8);
+    }
+
+    #[test]
+    fn pop_filled() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        assert_eq!(buf.filled_first(), None);
+        assert_eq!(buf.pop_filled(), None);
+
+        buf.unfilled().append(b"ab\n");
+        assert_eq!(buf.filled_first(), Some(b'a'));
+        assert_eq!(buf.filled_last(), Some(b'\n'));
+        assert_eq!(buf.pop_filled(), Some(b'\n'));
+        assert_eq!(buf.filled(), b"ab");
+        assert_eq!(buf.init_len(), 3);
+    }
+
+    #[test]
+    fn append_char() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+
+        assert!(cursor.append_char('a'));
+        assert!(cursor.append_char('é'));
+        assert!(!cursor.append_char('€'));
+        assert_eq!(cursor.capacity(), 1);
+        assert_eq!(buf.filled(), "aé".as_bytes());
+    }
+
+    #[test]
+    fn append_filled_of() {
+        let mut a = [MaybeUninit::uninit(); 8];
+        let mut a = BorrowBuf::from(&mut a[..]);
+        a.unfilled().append(b"frag");
+        let mut b = [MaybeUninit::uninit(); 8];
+        let mut b = BorrowBuf::from(&mut b[..]);
+        b.unfilled().append(b"ment");
+
+        assert_eq!(concat_filled(&[&a, &b]), b"fragment");
+        assert!(a.append_filled_of(&b));
+        assert_eq!(a.len(), 8);
+        assert_eq!(a.filled(), b"fragment");
+        assert!(!a.append_filled_of(&b));
+        assert_eq!(a.len(), 8);
+    }
+
+    #[test]
+    fn filled_windows() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3, 4, 5]);
+
+        let windows: Vec<_> = buf.filled_windows(3).collect();
+        assert_eq!(windows, [&[1, 2, 3], &[2, 3, 4], &[3, 4, 5]]);
+    }
+
+    #[test]
+    fn borrow_in_hash_set() {
+        use std::collections::HashSet;
+
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"key");
+        assert_eq!(buf.as_ref(), b"key");
+
+        let mut set = HashSet::new();
+        set.insert(buf);
+        assert!(set.contains(&b"key"[..]));
+        assert!(!set.contains(&b"ke"[..]));
+    }
+
+    #[test]
+    fn append_io_slices() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+
+        let slices = [io::IoSlice::new(b"head"), io::IoSlice::new(b"er")];
+        assert_eq!(cursor.append_io_slices(&slices), Ok(6));
+        assert!(cursor.append_io_slices(&slices).is_err());
+        assert_eq!(buf.filled(), b"header");
+    }
+
+    #[test]
+    fn record_mut() {
+        let mut backing = [MaybeUninit::uninit(); 6];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut record = buf.record_mut::<4>().unwrap();
+        assert_eq!(*record, [0; 4]);
+        *record = [1, 2, 3, 4];
+        record.commit();
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(buf.init_len(), 4);
+
+        // The record is zeroed, so the bytes stay initialized if it is not committed.
+        let mut record = buf.record_mut::<2>().unwrap();
+        record[0] = 5;
+        drop(record);
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.init_len(), 6);
+        assert_eq!(buf.initialized(), &[1, 2, 3, 4, 5, 0]);
+        assert!(buf.record_mut::<3>().is_none());
+
+        // Initialized bytes of the record are not zeroed again.
+        buf.clear();
+        assert_eq!(*buf.record_mut::<3>().unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn checkpoint_restore() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"ok:");
+        let mut byte = [0];
+        io::Read::read(&mut buf, &mut byte).unwrap();
+
+        // Speculatively parse a number, which fails.
+        let cp = buf.checkpoint();
+        buf.unfilled().append(b"12x");
+        io::Read::read(&mut buf, &mut byte).unwrap();
+        buf.restore(cp);
+
+        assert_eq!(buf.filled(), b"ok:");
+        assert_eq!(buf.init_len(), 6);
+        io::Read::read(&mut buf, &mut byte).unwrap();
+        assert_eq!(byte, *b"k");
+    }
+
+    #[test]
+    #[should_panic]
+    fn restore_foreign_checkpoint() {
+        let mut a = [MaybeUninit::uninit(); 8];
+        let mut a = BorrowBuf::from(&mut a[..]);
+        a.unfilled().append(&[1; 8]);
+        let mut b = [MaybeUninit::uninit(); 8];
+        let mut b = BorrowBuf::from(&mut b[..]);
+        b.restore(a.checkpoint());
+    }
+
+    #[test]
+    fn drain_into() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3, 4, 5]);
+
+        let mut dst = [0; 3];
+        assert_eq!(buf.drain_into(&mut dst), 3);
+        assert_eq!(dst, [1, 2, 3]);
+        assert_eq!(buf.drain_into(&mut dst), 2);
+        assert_eq!(dst[..2], [4, 5]);
+        assert_eq!(buf.drain_into(&mut dst), 0);
+        assert_eq!(buf.len(), 5);
+    }
+
+    #[test]
+    fn borrow_buf_macro() {
+        let backing = "not the macro's backing";
+        borrow_buf!(buf, 1024);
+        assert_eq!(buf.capacity(), 1024);
+        assert_eq!(buf.init_len(), 0);
+        buf.unfilled().append(&[1, 2]);
+        assert_eq!(buf.filled(), &[1, 2]);
+        assert_eq!(backing, "not the macro's backing");
+    }
+
+    #[test]
+    fn ensure_init_mut_partially_initialized() {
+        let mut backing = [MaybeUninit::new(9); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2]);
+        unsafe {
+            buf.set_init(5);
+        }
+
+        let mut cursor = buf.unfilled();
+        // The initialized bytes keep their contents, only the tail is zeroed.
+        assert_eq!(cursor.ensure_init_mut(), &[9, 9, 9, 0, 0, 0]);
+        assert_eq!(buf.init_len(), 8);
+    }
+
+    #[test]
+    fn initialized_unfilled_mut() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2]);
+        buf.unfilled().ensure_init();
+
+        buf.initialized_unfilled_mut().fill(0xAA);
+        assert_eq!(buf.unfilled().init_ref(), &[0xAA; 6]);
+
+        // A short read leaves the canary visible after the read bytes.
+        let n = read::read_buf(&mut &[3, 4][..], &mut buf.unfilled()).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(buf.initialized_unfilled_mut(), &[0xAA; 4]);
+    }
+
+    #[test]
+    fn process_and_clear() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        let sum = buf.process_and_clear(|bytes| {
+            assert_eq!(bytes, &[1, 2, 3]);
+            bytes.iter_mut().for_each(|b| *b *= 2);
+            bytes.iter().map(|&b| b as u32).sum::<u32>()
+        });
+        assert_eq!(sum, 12);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.init_len(), 3);
+        assert_eq!(buf.initialized(), &[2, 4, 6]);
+    }
+
+    #[test]
+    fn commit_read() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled();
+        let result = (&[1, 2, 3][..]).read(cursor.ensure_init_mut());
+        assert_eq!(cursor.commit_read(result).unwrap(), 3);
+
+        let result = Err(io::Error::new(io::ErrorKind::Other, "oops"));
+        assert_eq!(cursor.commit_read(result).unwrap_err().to_string(), "oops");
+        assert_eq!(cursor.written(), 3);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "read of 2 bytes exceeds the 0 initialized bytes of the cursor")]
+    fn commit_read_uninit() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let _ = buf.unfilled().commit_read(Ok(2));
+    }
+
+    #[test]
+    fn pipe_into() {
+        let mut a = [MaybeUninit::uninit(); 8];
+        let mut b = [MaybeUninit::uninit(); 3];
+        let mut src = BorrowBuf::from(&mut a[..]);
+        let mut dst = BorrowBuf::from(&mut b[..]);
+        src.extend_from_slice(&[1, 2, 3, 4, 5]);
+        src.read_exact(&mut [0]).unwrap();
+        assert_eq!(src.unread_len(), 4);
+
+        assert_eq!(src.pipe_into(&mut dst), 3);
+        assert_eq!(dst.filled(), &[2, 3, 4]);
+        assert_eq!(src.unread_len(), 1);
+        assert_eq!(src.pipe_into(&mut dst), 0);
+
+        dst.clear();
+        assert_eq!(src.pipe_into(&mut dst), 1);
+        assert_eq!(dst.filled(), &[5]);
+        assert_eq!(src.unread_len(), 0);
+    }
+
+    #[test]
+    fn into_filled_ref() {
+        fn fill(backing: &mut [MaybeUninit<u8>]) -> &[u8] {
+            let mut buf = BorrowBuf::from(backing);
+            buf.extend_from_slice(&[1, 2, 3]);
+            buf.into()
+        }
+
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let filled = fill(&mut backing);
+        assert_eq!(filled.len(), 3);
+        assert_eq!(filled, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn split_header_mut() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+
+        let (header, body) = cursor.split_header_mut::<3>().unwrap();
+        assert_eq!(body.len(), 5);
+        header.fill(MaybeUninit::new(1));
+        body[0].write(2);
+        unsafe {
+            cursor.advance(4);
+        }
+        assert_eq!(buf.filled(), &[1, 1, 1, 2]);
+
+        let mut cursor = buf.unfilled();
+        assert!(cursor.split_header_mut::<4>().unwrap().1.is_empty());
+        assert!(cursor.split_header_mut::<5>().is_none());
+    }
+
+    #[cfg(feature = "memchr")]
+    #[test]
+    fn find_filled() {
+        let mut backing = [MaybeUninit::new(b'\n'); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        unsafe {
+            buf.set_init(16);
+        }
+        buf.extend_from_slice(b"ab\ncd\nef");
+
+        assert_eq!(buf.find_filled(b'\n'), Some(2));
+        assert_eq!(buf.find_filled_from(b'\n', 3), Some(5));
+        assert_eq!(buf.rfind_filled(b'\n'), Some(5));
+        // The initialized bytes after the filled part are not searched.
+        assert_eq!(buf.find_filled_from(b'\n', 6), None);
+        assert_eq!(buf.find_filled_from(b'\n', 100), None);
+        assert_eq!(buf.find_filled(b'z'), None);
+    }
+
+    #[test]
+    fn sort_by_filled() {
+        let mut a = [MaybeUninit::uninit(); 4];
+        let mut b = [MaybeUninit::uninit(); 4];
+        let mut c = [MaybeUninit::uninit(); 4];
+        let mut bufs = vec![
+            BorrowBuf::from(&mut a[..]),
+            BorrowBuf::from(&mut b[..]),
+            BorrowBuf::from(&mut c[..]),
+        ];
+        bufs[0].extend_from_slice(&[2]);
+        bufs[1].extend_from_slice(&[1, 9]);
+        bufs[2].extend_from_slice(&[1]);
+        // Initialized but unfilled bytes do not count.
+        bufs[2].unfilled().ensure_init();
+
+        bufs.sort();
+        let sorted: Vec<&[u8]> = bufs.iter().map(|buf| buf.filled()).collect();
+        assert_eq!(sorted, [&[1][..], &[1, 9], &[2]]);
+    }
+
+    #[test]
+    fn fill_loop() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut calls = 0;
+
+        // Fills two bytes per call, to capacity.
+        let n = buf
+            .fill_loop(|mut cursor| {
+                calls += 1;
+                let mut reader: &[u8] = &[calls; 2];
+                read::read_buf(&mut reader, &mut cursor)
+            })
+            .unwrap();
+        assert_eq!(n, 8);
+        assert_eq!(calls, 4);
+        assert_eq!(buf.filled(), &[1, 1, 2, 2, 3, 3, 4, 4]);
+
+        // Stops at the end of the input.
+        buf.clear();
+        let mut reader: &[u8] = &[1, 2, 3];
+        let n = buf
+            .fill_loop(|mut cursor| read::read_buf(&mut reader, &mut cursor))
+            .unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn as_io_slices_mut() {
+        let mut backing = [MaybeUninit::uninit(); 10];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+
+        let mut slices = cursor.as_io_slices_mut(4);
+        let lens: Vec<usize> = slices.iter().map(|s| s.len()).collect();
+        assert_eq!(lens, [4, 4, 2]);
+
+        let n = (&[1, 2, 3, 4, 5, 6][..])
+            .read_vectored(&mut slices)
+            .unwrap();
+        drop(slices);
+        unsafe {
+            cursor.advance(n);
+        }
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn append_joined() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let parts: &[&[u8]] = &[b"ab", b"c", b"de"];
+
+        // Fits with one byte to spare, then exactly fills the buffer after one more byte.
+        assert_eq!(buf.unfilled().append_joined(parts, b"\n"), Ok(7));
+        assert_eq!(buf.filled(), b"ab\nc\nde");
+
+        buf.clear();
+        buf.extend_from_slice(b"x");
+        assert_eq!(buf.unfilled().append_joined(parts, b"\n"), Ok(7));
+        assert!(!buf.has_remaining());
+
+        buf.clear();
+        buf.extend_from_slice(b"xy");
+        assert_eq!(
+            buf.unfilled().append_joined(parts, b"\n"),
+            Err(CapacityError {
+                requested: 7,
+                available: 6
+            })
+        );
+        assert_eq!(buf.filled(), b"xy");
+        assert_eq!(buf.unfilled().append_joined(&[], b"\n"), Ok(0));
+    }
+
+    #[test]
+    fn fill_via() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1]);
+
+        buf.fill_via(|out| {
+            assert_eq!(out.len(), 7);
+            out[..3].copy_from_slice(&[2, 3, 4]);
+            3
+        });
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(buf.init_len(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot fill 5 bytes, only 4 available")]
+    fn fill_via_too_many() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.fill_via(|_| 5);
+    }
+
+    #[test]
+    fn get_and_set_filled() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2, 3, 4]);
+        buf.unfilled().ensure_init();
+
+        assert!(buf.set_filled(2, 9));
+        assert_eq!(buf.get_filled(2), Some(9));
+        assert_eq!(buf.filled(), &[1, 2, 9, 4]);
+
+        // Initialized but unfilled bytes are out of range.
+        assert_eq!(buf.get_filled(4), None);
+        assert!(!buf.set_filled(4, 9));
+        assert_eq!(buf.initialized()[4], 0);
+    }
+
+    #[test]
+    fn written_since() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        let mut cursor = buf.unfilled();
+        cursor.append(&[1]);
+
+        let mark = cursor.mark();
+        cursor.append(&[2, 3, 4]);
+        assert_eq!(cursor.written_since(mark), 3);
+        assert_eq!(cursor.written(), 4);
+
+        cursor.rewind(4);
+        assert_eq!(cursor.written_since(mark), 0);
+    }
+
+    #[test]
+    fn from_raw_parts() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        backing[..3].copy_from_slice(&[
+            MaybeUninit::new(1),
+            MaybeUninit::new(2),
+            MaybeUninit::new(3),
+        ]);
+        let ptr = backing.as_mut_ptr();
+
+        // As if the pointer had come from C, with two bytes used and three initialized.
+        let mut buf = unsafe { BorrowBuf::from_raw_parts(ptr, 8, 2, 3) };
+        assert_eq!(buf.filled(), &[1, 2]);
+        assert_eq!(buf.init_len(), 3);
+        assert_eq!(buf.capacity(), 8);
+
+        buf.extend_from_slice(&[4, 5]);
+        assert_eq!(buf.filled(), &[1, 2, 4, 5]);
+        let parts = buf.parts();
+        assert_eq!((parts.filled, parts.initialized, parts.capacity), (4, 4, 8));
+    }
+
+    #[test]
+    fn this_write() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2]);
+
+        let mut cursor = buf.unfilled();
+        assert_eq!(cursor.this_write(), &[]);
+        cursor.append(&[3, 4, 5, 6]);
+        assert_eq!(cursor.this_write(), &[3, 4, 5, 6]);
+        cursor.rewind(1);
+        assert_eq!(cursor.this_write(), &[3, 4, 5]);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn try_append() {
+        let mut backing = [MaybeUninit::uninit(); 4];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        buf.unfilled().try_append(&[1, 2, 3]).unwrap();
+        let err = buf.unfilled().try_append(&[4, 5]).unwrap_err();
+        assert_eq!(err.requested, 2);
+        assert_eq!(err.available, 1);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn filled_range() {
+        let mut backing = [0; 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3, 4]);
+
+        assert_eq!(buf.filled_range(..), &[1, 2, 3, 4]);
+        assert_eq!(buf.filled_range(1..3), &[2, 3]);
+        assert_eq!(buf.filled_range(2..=3), &[3, 4]);
+        assert_eq!(buf.filled_range(4..), &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 6 out of range for filled region of length 4")]
+    fn filled_range_out_of_bounds() {
+        let mut backing = [0; 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(&[1, 2, 3, 4]);
+
+        // In bounds of the backing slice, but not of the filled region.
+        buf.filled_range(2..6);
+    }
+
+    // Pins down the auto traits of the public types.
+    const _: fn() = || {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BorrowBuf<'static>>();
+        assert_send_sync::<BorrowCursor<'static, 'static>>();
+        assert_send_sync::<owned::VecCursor<'static>>();
+        assert_send_sync::<owned::PositionedVecCursor<'static>>();
+        assert_send_sync::<CapacityError>();
+    };
+
+    #[test]
+    fn fill_on_scoped_thread() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        std::thread::scope(|s| {
+            s.spawn(|| buf.unfilled().append(&[1, 2, 3]));
+        });
+        assert_eq!(buf.filled(), &[1, 2, 3]);
     }
 
     fn copy_to<R: Read + ?Sized>(reader: &mut R, mut buf: Vec<u8>) -> io::Result<usize> {
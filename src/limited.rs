@@ -0,0 +1,149 @@
+//! Cursors which assert that a reader stays within a logical boundary.
+
+use crate::BorrowCursor;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+
+/// A cursor which panics if more than a fixed number of bytes are filled through it.
+///
+/// Create one with [`BorrowCursor::limited`]. This is a debugging aid for frame parsers: where
+/// a frame says that exactly `limit` more bytes belong to it, a reader bug which would read into
+/// the next frame panics instead. Unlike `take`, which silently caps the cursor's capacity, the
+/// cursor's capacity is not changed.
+///
+/// Only the methods which fill bytes are checked, so the wrapped cursor can be read through
+/// `Deref` but not mutated directly; use `into_inner` to get it back.
+#[derive(Debug)]
+pub struct LimitedCursor<'a, 'b> {
+    cursor: BorrowCursor<'a, 'b>,
+    // `cursor.written()` when the limit was set.
+    start: usize,
+    limit: usize,
+}
+
+impl<'a, 'b> LimitedCursor<'a, 'b> {
+    pub(crate) fn new(cursor: BorrowCursor<'a, 'b>, limit: usize) -> LimitedCursor<'a, 'b> {
+        LimitedCursor {
+            start: cursor.written(),
+            cursor,
+            limit,
+        }
+    }
+
+    /// Returns the number of bytes which may still be filled before the limit is reached.
+    #[inline]
+    pub fn remaining_limit(&self) -> usize {
+        self.limit - (self.cursor.written() - self.start)
+    }
+
+    /// Returns the wrapped cursor, without a limit.
+    #[inline]
+    pub fn into_inner(self) -> BorrowCursor<'a, 'b> {
+        self.cursor
+    }
+
+    /// Returns a mutable reference to the initialized portion of the cursor.
+    #[inline]
+    pub fn init_mut(&mut self) -> &mut [u8] {
+        self.cursor.init_mut()
+    }
+
+    /// Returns a mutable reference to the uninitialized part of the cursor.
+    ///
+    /// It is safe to uninitialize any of these bytes.
+    #[inline]
+    pub fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.cursor.uninit_mut()
+    }
+
+    /// Initialises all bytes in the cursor.
+    #[inline]
+    pub fn ensure_init(&mut self) -> &mut Self {
+        self.cursor.ensure_init();
+        self
+    }
+
+    /// Increases the size of the filled region of the buffer, as for `BorrowCursor::advance`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `n` elements of the cursor have been properly
+    /// initialised.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than `self.remaining_limit()`.
+    #[inline]
+    #[track_caller]
+    pub unsafe fn advance(&mut self, n: usize) -> &mut Self {
+        self.check(n);
+        self.cursor.advance(n);
+        self
+    }
+
+    /// Appends data to the cursor, as for `BorrowCursor::append`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf.len()` is greater than `self.remaining_limit()` or `self.capacity()`.
+    #[inline]
+    #[track_caller]
+    pub fn append(&mut self, buf: &[u8]) {
+        self.check(buf.len());
+        self.cursor.append(buf);
+    }
+
+    #[track_caller]
+    fn check(&self, n: usize) {
+        assert!(
+            n <= self.remaining_limit(),
+            "cannot fill {} bytes, only {} of the limit of {} are left",
+            n,
+            self.remaining_limit(),
+            self.limit
+        );
+    }
+}
+
+impl<'a, 'b> Deref for LimitedCursor<'a, 'b> {
+    type Target = BorrowCursor<'a, 'b>;
+
+    fn deref(&self) -> &BorrowCursor<'a, 'b> {
+        &self.cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BorrowBuf;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn within_limit() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"hdr");
+
+        let mut cursor = buf.unfilled().limited(4);
+        assert_eq!(cursor.capacity(), 13);
+        cursor.append(b"ab");
+        cursor.ensure_init();
+        unsafe {
+            cursor.advance(2);
+        }
+        assert_eq!(cursor.remaining_limit(), 0);
+        cursor.into_inner().append(b"!");
+        assert_eq!(buf.filled(), b"hdrab\0\0!");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot fill 3 bytes, only 2 of the limit of 4 are left")]
+    fn past_limit() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut cursor = buf.unfilled().limited(4);
+        cursor.append(b"ab");
+        cursor.append(b"cde");
+    }
+}
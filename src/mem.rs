@@ -0,0 +1,122 @@
+//! Bulk initialization and copying of `MaybeUninit` bytes.
+//!
+//! With the `simd` feature these use portable SIMD with aligned stores, otherwise they fall back
+//! to loops which the compiler lowers to `memset` and `memcpy`.
+
+use std::mem::MaybeUninit;
+
+#[cfg(feature = "simd")]
+use std::simd::Simd;
+
+#[cfg(feature = "simd")]
+const LANES: usize = 64;
+
+/// Writes zero to every byte of `dst`.
+#[cfg(not(feature = "simd"))]
+#[inline]
+pub(crate) fn zero(dst: &mut [MaybeUninit<u8>]) {
+    for byte in dst {
+        byte.write(0);
+    }
+}
+
+/// Writes zero to every byte of `dst`.
+#[cfg(feature = "simd")]
+#[inline]
+pub(crate) fn zero(dst: &mut [MaybeUninit<u8>]) {
+    let (head, body, tail) = split_aligned(dst);
+    for byte in head.iter_mut().chain(tail) {
+        byte.write(0);
+    }
+
+    let zeros = Simd::<u8, LANES>::splat(0);
+    for chunk in body {
+        // SAFETY: `split_aligned` guarantees each chunk is a suitably aligned, in-bounds block of
+        // `LANES` bytes, and writing initialized bytes to `MaybeUninit` memory is always sound.
+        unsafe { (chunk.as_mut_ptr() as *mut Simd<u8, LANES>).write(zeros) };
+    }
+}
+
+/// Copies `src` into the start of `dst`.
+///
+/// # Panics
+///
+/// Panics if `dst` is shorter than `src`.
+#[cfg(not(feature = "simd"))]
+#[inline]
+pub(crate) fn copy(dst: &mut [MaybeUninit<u8>], src: &[u8]) {
+    MaybeUninit::write_slice(&mut dst[..src.len()], src);
+}
+
+/// Copies `src` into the start of `dst`.
+///
+/// # Panics
+///
+/// Panics if `dst` is shorter than `src`.
+#[cfg(feature = "simd")]
+#[inline]
+pub(crate) fn copy(dst: &mut [MaybeUninit<u8>], src: &[u8]) {
+    let (head, body, tail) = split_aligned(&mut dst[..src.len()]);
+    let (src_head, src) = src.split_at(head.len());
+    let (src_body, src_tail) = src.split_at(body.len() * LANES);
+
+    MaybeUninit::write_slice(head, src_head);
+    MaybeUninit::write_slice(tail, src_tail);
+    for (chunk, src) in body.iter_mut().zip(src_body.chunks_exact(LANES)) {
+        // `src` has no particular alignment, so load unaligned and store aligned.
+        let v = Simd::<u8, LANES>::from_slice(src);
+        // SAFETY: as for `zero`.
+        unsafe { (chunk.as_mut_ptr() as *mut Simd<u8, LANES>).write(v) };
+    }
+}
+
+#[cfg(feature = "simd")]
+type Split<'a> = (
+    &'a mut [MaybeUninit<u8>],
+    &'a mut [[MaybeUninit<u8>; LANES]],
+    &'a mut [MaybeUninit<u8>],
+);
+
+// Splits `dst` into an unaligned head, a body of blocks aligned for `Simd<u8, LANES>`, and a tail
+// of fewer than `LANES` bytes.
+#[cfg(feature = "simd")]
+#[inline]
+fn split_aligned(dst: &mut [MaybeUninit<u8>]) -> Split<'_> {
+    let offset = dst
+        .as_ptr()
+        .align_offset(std::mem::align_of::<Simd<u8, LANES>>());
+    if offset > dst.len() {
+        return (dst, &mut [], &mut []);
+    }
+
+    let (head, rest) = dst.split_at_mut(offset);
+    let (body, tail) = rest.as_chunks_mut::<LANES>();
+    (head, body, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_and_copy() {
+        let src: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        // Exercise every alignment of the destination relative to a SIMD block.
+        for start in 0..64 {
+            let mut dst = vec![MaybeUninit::new(0xff); 1100];
+            let dst = &mut dst[start..];
+
+            zero(&mut dst[..1000]);
+            assert!(dst[..1000].iter().all(|b| unsafe { b.assume_init() } == 0));
+            assert_eq!(unsafe { dst[1000].assume_init() }, 0xff);
+
+            copy(dst, &src);
+            let copied: Vec<u8> = dst[..1000]
+                .iter()
+                .map(|b| unsafe { b.assume_init() })
+                .collect();
+            assert_eq!(copied, src);
+            assert_eq!(unsafe { dst[1000].assume_init() }, 0xff);
+        }
+    }
+}
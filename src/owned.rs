@@ -1,11 +1,41 @@
+use crate::mem;
 use std::cmp;
+use std::convert::Infallible;
+use std::io::{self, Read};
 use std::mem::MaybeUninit;
 
+mod arena;
+mod borrow;
+#[cfg(feature = "heapless")]
+mod heapless;
+mod io_cursor;
+#[cfg(feature = "memmap2")]
+mod mmap;
+mod vec_buf;
+
+pub use self::arena::{ArenaBuf, ArenaCursor, UninitBox};
+#[cfg(feature = "heapless")]
+pub use self::heapless::HeaplessCursor;
+pub use self::io_cursor::{PositionedSliceCursor, PositionedVecCursor};
+#[cfg(feature = "memmap2")]
+pub use self::mmap::MmapBuf;
+pub use self::vec_buf::{VecBuf, VecBufCursor, VecSpareBuf};
+
 pub trait OwnedBuf {
     type Cursor<'b>: OwnedCursor<'b>
     where
         Self: 'b;
 
+    /// The error returned when the buffer cannot make room for more data.
+    ///
+    /// Buffers which can grow use `Infallible`.
+    type Error;
+
+    /// The iterator returned by `drain_filled`.
+    type Drain<'d>: Iterator<Item = u8>
+    where
+        Self: 'd;
+
     /// Returns the total capacity of the buffer.
     fn capacity(&self) -> usize;
 
@@ -18,9 +48,43 @@ pub trait OwnedBuf {
     /// Returns a shared reference to the filled portion of the buffer.
     fn filled(&self) -> &[u8];
 
+    /// Returns a mutable reference to the filled portion of the buffer.
+    fn filled_mut(&mut self) -> &mut [u8];
+
     /// Returns a cursor over the unfilled part of the buffer.
     fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b>;
 
+    /// Returns a cursor over only the initialized part of the unfilled buffer.
+    ///
+    /// The cursor's capacity is the number of initialized but unfilled bytes, so reading into it never
+    /// requires zeroing memory. If there are no such bytes, the cursor has zero capacity.
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b>;
+
+    /// Consumes the buffer, returning its filled bytes as a `Vec`.
+    ///
+    /// Buffers which are backed by a `Vec` return it (truncated to the filled part) without
+    /// copying; other buffers copy their filled bytes.
+    fn into_filled_vec(self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        self.filled().to_vec()
+    }
+
+    /// Calls `f` with a cursor over the unfilled part of the buffer.
+    ///
+    /// This avoids having to name `Self::Cursor<'_>` in generic code.
+    fn with_cursor<R>(&mut self, f: impl FnOnce(Self::Cursor<'_>) -> R) -> R {
+        f(self.unfilled())
+    }
+
+    /// Removes the filled bytes from the buffer, returning them as an iterator.
+    ///
+    /// As for `Vec::drain(..)`, all filled bytes are removed when the iterator is dropped, even if
+    /// it was not run to the end. Draining is otherwise the same as `clear`: the number of
+    /// initialized bytes is not changed.
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d>;
+
     /// Clears the buffer, resetting the filled region to empty.
     ///
     /// The number of initialized bytes is not changed, and the contents of the buffer are not modified.
@@ -35,6 +99,23 @@ pub trait OwnedBuf {
     ///
     /// The caller must ensure that the first `n` unfilled bytes of the buffer have already been initialized.
     unsafe fn set_init(&mut self, n: usize) -> &mut Self;
+
+    /// Ensures there is space for at least `additional` more bytes to be filled.
+    ///
+    /// Buffers which can grow will do so; fixed-size buffers return an error if there is not
+    /// enough space left.
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Self::Error>;
+
+    /// Grows the filled part of the buffer by `n` bytes.
+    ///
+    /// Returns an error (and does not change the buffer) if there are fewer than `n` unfilled
+    /// bytes.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `n` unfilled bytes of the buffer have been properly
+    /// initialised.
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), Self::Error>;
 }
 
 pub trait OwnedCursor<'a> {
@@ -48,6 +129,11 @@ pub trait OwnedCursor<'a> {
     /// Returns the available space in the cursor.
     fn capacity(&self) -> usize;
 
+    /// Returns `true` if there is no space left in the cursor.
+    fn is_full(&self) -> bool {
+        self.capacity() == 0
+    }
+
     /// Returns the number of bytes written to this cursor since it was created from a `BorrowBuf`.
     ///
     /// Note that if this cursor is a clone of another, then the count returned is the count written
@@ -66,6 +152,15 @@ pub trait OwnedCursor<'a> {
     /// It is safe to uninitialize any of these bytes.
     fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>];
 
+    /// Returns the lengths of the initialized and uninitialized parts of the cursor.
+    ///
+    /// The lengths always add up to `self.capacity()`.
+    // TODO shouldn't need mut self, same as init_ref
+    fn region_lens(&mut self) -> (usize, usize) {
+        let init = self.init_ref().len();
+        (init, self.capacity() - init)
+    }
+
     /// Returns a mutable reference to the whole cursor.
     ///
     /// # Safety
@@ -73,6 +168,13 @@ pub trait OwnedCursor<'a> {
     /// The caller must not uninitialize any bytes in the initialized portion of the cursor.
     unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>];
 
+    /// Returns a raw pointer to the start of the cursor and the number of bytes available there.
+    ///
+    /// This is intended for passing the spare capacity of the buffer to a system call such as
+    /// `recv` or `recvmsg`. Any growth or reallocation of the underlying buffer invalidates a
+    /// previously returned pointer.
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize);
+
     /// Advance the cursor by asserting that `n` bytes have been filled.
     ///
     /// After advancing, the `n` bytes are no longer accessible via the cursor and can only be
@@ -98,17 +200,124 @@ pub trait OwnedCursor<'a> {
     /// The caller must ensure that the first `n` bytes of the buffer have already been initialized.
     unsafe fn set_init(&mut self, n: usize);
 
+    /// Forgets that bytes of the cursor past the first `n` are initialized.
+    ///
+    /// The number of initialized bytes becomes the smaller of the current count and `n`. The
+    /// contents of the cursor are not modified, but a later `ensure_init` will zero the
+    /// forgotten bytes again. This is useful where stale data must not be exposed through
+    /// `init_ref` or `init_mut`.
+    ///
+    /// Only bytes which are spare memory of the underlying buffer are forgotten, since forgotten
+    /// bytes can be uninitialized through `uninit_mut`. Bytes which are initialized memory of the
+    /// buffer itself, such as the elements of a `Vec` after a `Cursor`'s position or a `BorrowBuf`
+    /// over a `&mut [u8]`, stay initialized, so the count may stay above `n`.
+    fn reset_init(&mut self, n: usize);
+
     /// Appends data to the cursor, advancing position within its buffer.
     ///
     /// # Panics
     ///
     /// Panics if `self.capacity()` is less than `buf.len()`.
     fn append(&mut self, buf: &[u8]);
+
+    /// Reads from `r` into the cursor, advancing it by the number of bytes read.
+    ///
+    /// The whole cursor is initialized before reading, since `Read::read` requires an initialized
+    /// buffer. Cursors over growable buffers may grow the buffer first if the cursor is full.
+    fn read_from<R: Read + ?Sized>(&mut self, r: &mut R) -> io::Result<usize>
+    where
+        Self: Sized,
+    {
+        read_into_init(self, r)
+    }
+}
+
+/// An iterator over the filled bytes of an [`OwnedBuf`], which clears the buffer when dropped.
+///
+/// See [`OwnedBuf::drain_filled`].
+pub struct FilledDrain<'d, B: OwnedBuf + ?Sized> {
+    buf: &'d mut B,
+    pos: usize,
+}
+
+impl<'d, B: OwnedBuf + ?Sized> FilledDrain<'d, B> {
+    /// Creates an iterator over the filled bytes of `buf`.
+    pub fn new(buf: &'d mut B) -> FilledDrain<'d, B> {
+        FilledDrain { buf, pos: 0 }
+    }
+}
+
+impl<B: OwnedBuf + ?Sized> Iterator for FilledDrain<'_, B> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = *self.buf.filled().get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.buf.len() - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl<B: OwnedBuf + ?Sized> ExactSizeIterator for FilledDrain<'_, B> {}
+
+impl<B: OwnedBuf + ?Sized> Drop for FilledDrain<'_, B> {
+    fn drop(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Compares the filled parts of two buffers lexicographically, the same as `<[u8]>::cmp`.
+///
+/// The buffers may be of different types, e.g., a `Vec<u8>` and a `VecBuf`.
+pub fn cmp_filled<A: OwnedBuf + ?Sized, B: OwnedBuf + ?Sized>(a: &A, b: &B) -> cmp::Ordering {
+    a.filled().cmp(b.filled())
 }
 
-// Note that the initialized count is not preserved between cursors.
+// Reads into the cursor once, without growing it.
+fn read_into_init<'a, C: OwnedCursor<'a>, R: Read + ?Sized>(
+    cursor: &mut C,
+    r: &mut R,
+) -> io::Result<usize> {
+    cursor.ensure_init();
+    let buf = cursor.init_mut();
+    let len = buf.len();
+    let n = r.read(buf)?;
+    assert!(
+        n <= len,
+        "reader returned more bytes than the buffer can hold"
+    );
+    unsafe {
+        // SAFETY: the whole cursor was initialized above
+        cursor.advance(n);
+    }
+    Ok(n)
+}
+
+// Grows `vec` if a cursor over its spare capacity ending at `end` is full, so that reading into a
+// `Vec` with no spare capacity makes progress, and moves `end` to the new end of the allocation. A
+// cursor which was limited to less than the `Vec`'s capacity is never grown. Returns `true` if
+// the `Vec` was reallocated, in which case its spare capacity is no longer initialized.
+fn grow_if_full(vec: &mut Vec<u8>, end: &mut usize) -> bool {
+    if vec.len() != *end || *end != vec.capacity() {
+        return false;
+    }
+
+    let capacity = vec.capacity();
+    // Same as the probe size used by `Read::read_to_end`.
+    vec.reserve(32);
+    *end = vec.capacity();
+    vec.capacity() != capacity
+}
+
+// Note that the initialized count is not preserved between cursors; use `VecBuf` for that.
 impl OwnedBuf for Vec<u8> {
     type Cursor<'b> = VecCursor<'b>;
+    type Error = Infallible;
+    type Drain<'d> = std::vec::Drain<'d, u8>;
 
     fn capacity(&self) -> usize {
         self.capacity()
@@ -126,31 +335,87 @@ impl OwnedBuf for Vec<u8> {
         &**self
     }
 
+    fn filled_mut(&mut self) -> &mut [u8] {
+        &mut self[..]
+    }
+
+    // The spare capacity of a `Vec` is never known to be initialized, even if the `Vec` is not
+    // empty.
     fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
         VecCursor {
-            initialized: self.len(),
+            initialized: 0,
             start: self.len(),
+            end: self.capacity(),
             buf: self,
         }
     }
 
+    // The spare capacity of a `Vec` is never known to be initialized, so the cursor is always empty.
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        VecCursor {
+            initialized: 0,
+            start: self.len(),
+            end: self.len(),
+            buf: self,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        self.drain(..)
+    }
+
     fn clear(&mut self) -> &mut Self {
         self.clear();
         self
     }
 
+    fn into_filled_vec(self) -> Vec<u8> {
+        self
+    }
+
     unsafe fn set_init(&mut self, n: usize) -> &mut Self {
         let len = self.len();
         self.set_len(cmp::max(len, n));
         self
     }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Infallible> {
+        self.reserve(additional);
+        Ok(())
+    }
+
+    // A `Vec` can always grow, so there is no error to report; advancing past the capacity is a
+    // bug in the caller and panics.
+    #[track_caller]
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), Infallible> {
+        let len = self.len();
+        assert!(
+            n <= self.capacity() - len,
+            "advance past the capacity of the Vec"
+        );
+        self.set_len(len + n);
+        Ok(())
+    }
 }
 
+/// A cursor over the spare capacity of a `Vec<u8>`.
+///
+/// `VecCursor` is `Send` and `Sync`.
 pub struct VecCursor<'a> {
     buf: &'a mut Vec<u8>,
     // relative to len of buf (not 0)
     initialized: usize,
     start: usize,
+    // absolute, at most the capacity of buf
+    end: usize,
+}
+
+impl<'a> VecCursor<'a> {
+    // The part of the spare capacity covered by the cursor.
+    fn spare(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.buf.len();
+        &mut self.buf.spare_capacity_mut()[..self.end - len]
+    }
 }
 
 impl<'a> OwnedCursor<'a> for VecCursor<'a> {
@@ -159,11 +424,12 @@ impl<'a> OwnedCursor<'a> for VecCursor<'a> {
             buf: self.buf,
             initialized: self.initialized,
             start: self.start,
+            end: self.end,
         })
     }
 
     fn capacity(&self) -> usize {
-        self.buf.capacity() - self.buf.len()
+        self.end - self.buf.len()
     }
 
     fn written(&self) -> usize {
@@ -171,51 +437,295 @@ impl<'a> OwnedCursor<'a> for VecCursor<'a> {
     }
 
     fn init_ref(&mut self) -> &[u8] {
-        unsafe {
-            MaybeUninit::slice_assume_init_ref(&self.buf.spare_capacity_mut()[..self.initialized])
-        }
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.spare()[..initialized]) }
     }
 
     fn init_mut(&mut self) -> &mut [u8] {
-        unsafe {
-            MaybeUninit::slice_assume_init_mut(
-                &mut self.buf.spare_capacity_mut()[..self.initialized],
-            )
-        }
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.spare()[..initialized]) }
     }
 
     fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
-        &mut self.buf.spare_capacity_mut()[self.initialized..]
+        let initialized = self.initialized;
+        &mut self.spare()[initialized..]
     }
 
     unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
-        self.buf.spare_capacity_mut()
+        self.spare()
     }
 
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let spare = self.spare();
+        (spare.as_mut_ptr() as *mut u8, spare.len())
+    }
+
+    #[track_caller]
     unsafe fn advance(&mut self, n: usize) {
         let len = self.buf.len();
+        // `end` is at most the capacity, so this also keeps `set_len` within the capacity.
+        assert!(n <= self.end - len, "advance past the end of the cursor");
         self.buf.set_len(len + n);
+        self.initialized = self.initialized.saturating_sub(n);
     }
 
     fn ensure_init(&mut self) {
-        for byte in self.uninit_mut() {
-            byte.write(0);
-        }
+        mem::zero(self.uninit_mut());
 
-        self.initialized = self.buf.capacity();
+        self.initialized = self.capacity();
     }
 
     unsafe fn set_init(&mut self, n: usize) {
         self.initialized = cmp::max(self.initialized, n);
     }
 
+    fn reset_init(&mut self, n: usize) {
+        self.initialized = cmp::min(self.initialized, n);
+    }
+
+    #[track_caller]
     fn append(&mut self, buf: &[u8]) {
-        let spare = self.buf.spare_capacity_mut();
-        assert!(buf.len() <= spare.len());
-        MaybeUninit::write_slice(&mut spare[..buf.len()], buf);
+        if buf.is_empty() {
+            return;
+        }
+
+        let spare = self.spare();
+        assert!(
+            buf.len() <= spare.len(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            spare.len()
+        );
+        mem::copy(spare, buf);
         unsafe {
             // SAFETY we just wrote buf.len() bytes
             self.advance(buf.len());
         }
     }
+
+    // Grows the `Vec` if it is full, see `grow_if_full`.
+    fn read_from<R: Read + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        if grow_if_full(self.buf, &mut self.end) {
+            self.initialized = 0;
+        }
+        read_into_init(self, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spare_ptr_len() {
+        let mut v = Vec::with_capacity(16);
+        v.extend_from_slice(&[1, 2, 3]);
+        let mut cursor = v.unfilled();
+
+        let (ptr, len) = cursor.spare_ptr_len();
+        assert_eq!(len, cursor.capacity());
+        assert_eq!(ptr, cursor.uninit_mut().as_mut_ptr() as *mut u8);
+    }
+
+    #[test]
+    fn vec_spare_capacity_is_uninit() {
+        let mut v = Vec::with_capacity(16);
+        v.extend_from_slice(&[1, 2, 3]);
+        let mut cursor = v.unfilled();
+        assert!(cursor.init_ref().is_empty());
+        assert_eq!(cursor.uninit_mut().len(), cursor.capacity());
+    }
+
+    #[test]
+    fn region_lens() {
+        let mut c = io::Cursor::new(Vec::with_capacity(8));
+        c.get_mut().extend_from_slice(&[1, 2, 3]);
+
+        let mut cursor = c.unfilled();
+        let (init, uninit) = cursor.region_lens();
+        assert_eq!(init, 3);
+        assert_eq!(init + uninit, cursor.capacity());
+    }
+
+    #[test]
+    fn reset_init() {
+        let mut v = Vec::with_capacity(8);
+        let mut cursor = v.unfilled();
+        cursor.ensure_init();
+        cursor.init_mut().fill(7);
+
+        cursor.reset_init(2);
+        assert_eq!(cursor.init_ref(), &[7, 7]);
+        // Resetting to more than is initialized does nothing.
+        cursor.reset_init(100);
+        assert_eq!(cursor.init_ref().len(), 2);
+
+        cursor.ensure_init();
+        let init = cursor.init_ref();
+        assert_eq!(&init[..2], &[7, 7]);
+        assert!(init[2..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "advance past the end of the cursor")]
+    fn advance_past_capacity() {
+        let mut v = Vec::with_capacity(4);
+        let mut cursor = v.unfilled();
+        let n = cursor.capacity() + 1;
+        cursor.ensure_init();
+        unsafe { cursor.advance(n) };
+    }
+
+    #[test]
+    fn cmp_filled() {
+        let v = vec![1, 2];
+        let mut buf = VecBuf::with_capacity(4);
+        buf.unfilled().append(&[1, 3]);
+        assert_eq!(super::cmp_filled(&v, &buf), cmp::Ordering::Less);
+        assert_eq!(super::cmp_filled(&buf, &buf), cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn drain_half() {
+        let mut v = vec![1, 2, 3, 4];
+        let drained: Vec<u8> = v.drain_filled().take(2).collect();
+        assert_eq!(drained, [1, 2]);
+        assert!(v.is_empty());
+
+        let mut buf = VecBuf::with_capacity(8);
+        buf.unfilled().append(&[1, 2, 3, 4]);
+        buf.unfilled().ensure_init();
+        let init_len = buf.init_len();
+        let mut drain = buf.drain_filled();
+        assert_eq!(drain.len(), 4);
+        assert_eq!(drain.next(), Some(1));
+        assert_eq!(drain.next(), Some(2));
+        drop(drain);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.init_len(), init_len);
+    }
+
+    #[test]
+    fn is_full() {
+        let mut v = Vec::with_capacity(4);
+        let mut cursor = v.unfilled();
+        assert!(!cursor.is_full());
+        cursor.append(&vec![1; cursor.capacity()]);
+        assert!(cursor.is_full());
+    }
+
+    #[test]
+    fn append_empty_and_exactly_full() {
+        let mut v = vec![1, 2];
+        v.shrink_to_fit();
+        let mut cursor = v.unfilled();
+        cursor.append(&[]);
+        assert_eq!(cursor.written(), 0);
+
+        let mut v = Vec::with_capacity(4);
+        let mut cursor = v.unfilled();
+        cursor.ensure_init();
+        let data = vec![7; cursor.capacity()];
+        cursor.append(&data);
+        assert!(cursor.is_full());
+        cursor.append(&[]);
+        assert_eq!(v, data);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot append 1 bytes, only 0 available")]
+    fn append_past_capacity() {
+        let mut v = Vec::with_capacity(4);
+        let mut cursor = v.unfilled();
+        let data = vec![7; cursor.capacity()];
+        cursor.append(&data);
+        cursor.append(&[8]);
+    }
+
+    #[test]
+    fn read_from() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut v = Vec::with_capacity(4);
+
+        let mut cursor = v.unfilled();
+        assert_eq!(cursor.read_from(&mut reader).unwrap(), 4);
+        assert_eq!(cursor.read_from(&mut reader).unwrap(), 2);
+        assert_eq!(cursor.read_from(&mut reader).unwrap(), 0);
+        assert_eq!(cursor.written(), 6);
+        assert_eq!(v, &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn unfilled_init_only() {
+        let mut v = Vec::with_capacity(16);
+        v.extend_from_slice(&[1, 2, 3]);
+
+        let mut cursor = v.unfilled_init_only();
+        assert_eq!(cursor.capacity(), 0);
+        assert!(cursor.uninit_mut().is_empty());
+        assert_eq!(cursor.read_from(&mut &[4, 5][..]).unwrap(), 0);
+        assert_eq!(v, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn with_cursor() {
+        fn fill<B: OwnedBuf>(buf: &mut B, data: &[u8]) -> usize {
+            buf.with_cursor(|mut cursor| {
+                cursor.append(data);
+                cursor.written()
+            })
+        }
+
+        let mut v = Vec::with_capacity(8);
+        assert_eq!(fill(&mut v, &[1, 2, 3]), 3);
+        assert_eq!(v, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn into_filled_vec() {
+        fn fill<B: OwnedBuf>(mut buf: B) -> Vec<u8> {
+            buf.unfilled().append(&[1, 2, 3]);
+            buf.into_filled_vec()
+        }
+
+        let v = Vec::with_capacity(8);
+        let ptr = v.as_ptr();
+        let v = fill(v);
+        assert_eq!(v, &[1, 2, 3]);
+        assert_eq!(v.as_ptr(), ptr);
+
+        let mut c = io::Cursor::new(vec![9; 6]);
+        c.set_position(1);
+        assert_eq!(fill(c), &[9, 1, 2, 3]);
+
+        let mut storage = [0; 4];
+        let c = io::Cursor::new(&mut storage[..]);
+        assert_eq!(fill(c), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn filled_mut() {
+        fn double<B: OwnedBuf>(buf: &mut B) {
+            for b in buf.filled_mut() {
+                *b *= 2;
+            }
+        }
+
+        let mut v = vec![1, 2, 3];
+        double(&mut v);
+        assert_eq!(OwnedBuf::filled(&v), &[2, 4, 6]);
+    }
+
+    #[test]
+    fn vec_try_reserve_advance() {
+        let mut v = Vec::new();
+        OwnedBuf::try_reserve(&mut v, 8).unwrap();
+        assert!(OwnedBuf::capacity(&v) >= 8);
+
+        v.unfilled().ensure_init();
+        unsafe {
+            v.try_advance(8).unwrap();
+        }
+        assert_eq!(OwnedBuf::filled(&v), &[0; 8]);
+    }
 }
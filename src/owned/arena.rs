@@ -0,0 +1,327 @@
+use super::{FilledDrain, OwnedBuf, OwnedCursor};
+use crate::{mem, CapacityError};
+use std::cmp;
+use std::mem::MaybeUninit;
+use std::ops::DerefMut;
+
+/// An owned buffer over memory provided by a guard, such as an allocation from an arena or a
+/// memory-mapped region.
+///
+/// `ArenaBuf` owns the filled and initialized state of the buffer, while `G` owns the memory. When
+/// the `ArenaBuf` is dropped, `G` is dropped and can return the memory to wherever it came from.
+/// Use `into_inner` to get the guard back instead.
+///
+/// The memory is never grown, so reserving or advancing past the end is an error.
+pub struct ArenaBuf<G> {
+    storage: G,
+    filled: usize,
+    initialized: usize,
+    // Bytes before this are initialized memory of the storage itself (e.g., a memory map), so
+    // `reset_init` never forgets them.
+    pinned: usize,
+}
+
+impl<G: DerefMut<Target = [MaybeUninit<u8>]>> ArenaBuf<G> {
+    /// Creates a new, empty `ArenaBuf` over the memory of `storage`.
+    ///
+    /// The memory is assumed to be uninitialized; use `set_init` if some of it is known to be
+    /// initialized.
+    ///
+    /// # Safety
+    ///
+    /// Dereferencing `storage` must always produce the same slice (same address and length), and
+    /// the memory must remain valid until `storage` is dropped.
+    pub unsafe fn new(storage: G) -> ArenaBuf<G> {
+        ArenaBuf {
+            storage,
+            filled: 0,
+            initialized: 0,
+            pinned: 0,
+        }
+    }
+
+    // Never forgets the bytes which are currently initialized, for storage which is always
+    // initialized.
+    #[cfg(feature = "memmap2")]
+    pub(super) fn pin_init(&mut self) {
+        self.pinned = self.initialized;
+    }
+
+    /// Returns a shared reference to the guard owning the memory of this buffer.
+    pub fn get_ref(&self) -> &G {
+        &self.storage
+    }
+
+    /// Returns the guard owning the memory of this buffer.
+    pub fn into_inner(self) -> G {
+        self.storage
+    }
+}
+
+/// An owned buffer over a boxed slice which is never zeroed up front.
+///
+/// Unlike `Vec<u8>` created with `vec![0; n]`, creating an `UninitBox` does not touch its memory;
+/// bytes are only initialized as they are read into or by `ensure_init`. `filled` only exposes the
+/// bytes which have actually been filled.
+pub type UninitBox = ArenaBuf<Box<[MaybeUninit<u8>]>>;
+
+impl UninitBox {
+    /// Creates a new, empty buffer with `capacity` bytes of uninitialized memory.
+    pub fn with_capacity(capacity: usize) -> UninitBox {
+        // SAFETY: a `Box` always derefs to the same slice, and owns its memory.
+        unsafe { ArenaBuf::new(Box::new_uninit_slice(capacity)) }
+    }
+}
+
+impl<G: DerefMut<Target = [MaybeUninit<u8>]>> OwnedBuf for ArenaBuf<G> {
+    type Cursor<'b>
+        = ArenaCursor<'b>
+    where
+        G: 'b;
+    type Error = CapacityError;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        self.storage.len()
+    }
+
+    fn len(&self) -> usize {
+        self.filled
+    }
+
+    fn init_len(&self) -> usize {
+        self.initialized
+    }
+
+    fn filled(&self) -> &[u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.storage[..self.filled]) }
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        //SAFETY: We only slice the filled part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.storage[..self.filled]) }
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        ArenaCursor {
+            buf: &mut self.storage,
+            start: self.filled,
+            filled: &mut self.filled,
+            initialized: &mut self.initialized,
+            pinned: self.pinned,
+        }
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        ArenaCursor {
+            buf: &mut self.storage[..self.initialized],
+            start: self.filled,
+            filled: &mut self.filled,
+            initialized: &mut self.initialized,
+            pinned: self.pinned,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.filled = 0;
+        self
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        self.initialized = cmp::max(self.initialized, n);
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), CapacityError> {
+        let available = self.capacity() - self.filled;
+        if additional > available {
+            return Err(CapacityError {
+                requested: additional,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), CapacityError> {
+        self.try_reserve(n)?;
+        self.filled += n;
+        self.initialized = cmp::max(self.initialized, self.filled);
+        Ok(())
+    }
+}
+
+/// A cursor over the unfilled part of an `ArenaBuf`.
+pub struct ArenaCursor<'a> {
+    // The buffer's memory up to the end of the cursor, not just the unfilled part.
+    buf: &'a mut [MaybeUninit<u8>],
+    filled: &'a mut usize,
+    initialized: &'a mut usize,
+    start: usize,
+    // See `ArenaBuf::pinned`.
+    pinned: usize,
+}
+
+impl<'a> OwnedCursor<'a> for ArenaCursor<'a> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(ArenaCursor {
+            buf: self.buf,
+            filled: self.filled,
+            initialized: self.initialized,
+            start: self.start,
+            pinned: self.pinned,
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.buf.len() - *self.filled
+    }
+
+    fn written(&self) -> usize {
+        *self.filled - self.start
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        //SAFETY: We only slice the initialized part of the buffer, which is always valid
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[*self.filled..*self.initialized]) }
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        //SAFETY: We only slice the initialized part of the buffer, which is always valid
+        unsafe {
+            MaybeUninit::slice_assume_init_mut(&mut self.buf[*self.filled..*self.initialized])
+        }
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        &mut self.buf[*self.initialized..]
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        &mut self.buf[*self.filled..]
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let spare = &mut self.buf[*self.filled..];
+        (spare.as_mut_ptr() as *mut u8, spare.len())
+    }
+
+    #[track_caller]
+    unsafe fn advance(&mut self, n: usize) {
+        assert!(n <= self.capacity(), "advance past the end of the cursor");
+        *self.filled += n;
+        *self.initialized = cmp::max(*self.initialized, *self.filled);
+    }
+
+    fn ensure_init(&mut self) {
+        mem::zero(self.uninit_mut());
+
+        *self.initialized = cmp::max(*self.initialized, self.buf.len());
+    }
+
+    unsafe fn set_init(&mut self, n: usize) {
+        debug_assert!(n <= self.capacity(), "set_init past the end of the cursor");
+        *self.initialized = cmp::max(*self.initialized, *self.filled + n);
+    }
+
+    fn reset_init(&mut self, n: usize) {
+        let n = cmp::max(*self.filled + n, self.pinned);
+        *self.initialized = cmp::min(*self.initialized, n);
+    }
+
+    #[track_caller]
+    fn append(&mut self, buf: &[u8]) {
+        assert!(
+            buf.len() <= self.capacity(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            self.capacity()
+        );
+        mem::copy(&mut self.buf[*self.filled..], buf);
+        unsafe {
+            // SAFETY we just wrote buf.len() bytes
+            self.advance(buf.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::ops::Deref;
+
+    // Stands in for an allocation from an arena, which records when it is given back.
+    struct Allocation<'a> {
+        mem: Box<[MaybeUninit<u8>]>,
+        freed: &'a Cell<bool>,
+    }
+
+    impl Deref for Allocation<'_> {
+        type Target = [MaybeUninit<u8>];
+
+        fn deref(&self) -> &[MaybeUninit<u8>] {
+            &self.mem
+        }
+    }
+
+    impl DerefMut for Allocation<'_> {
+        fn deref_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+            &mut self.mem
+        }
+    }
+
+    impl Drop for Allocation<'_> {
+        fn drop(&mut self) {
+            self.freed.set(true);
+        }
+    }
+
+    #[test]
+    fn fill_and_free() {
+        let freed = Cell::new(false);
+        let alloc = Allocation {
+            mem: Box::new_uninit_slice(8),
+            freed: &freed,
+        };
+        // SAFETY: `Allocation` always derefs to the same boxed slice.
+        let mut buf = unsafe { ArenaBuf::new(alloc) };
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(buf.init_len(), 0);
+
+        let mut cursor = buf.unfilled();
+        cursor.append(&[1, 2, 3]);
+        cursor.ensure_init();
+        assert_eq!(cursor.init_ref(), &[0; 5]);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+        assert_eq!(buf.init_len(), 8);
+        assert!(buf.try_reserve(6).is_err());
+
+        let mut cursor = buf.unfilled_init_only();
+        assert_eq!(cursor.capacity(), 5);
+        cursor.append(&[4, 5]);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5]);
+
+        drop(buf);
+        assert!(freed.get());
+    }
+
+    #[test]
+    fn uninit_box() {
+        let mut buf = UninitBox::with_capacity(1 << 20);
+        assert_eq!(buf.capacity(), 1 << 20);
+        assert_eq!(buf.init_len(), 0);
+
+        buf.unfilled().append(&[1, 2, 3]);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+        assert_eq!(buf.init_len(), 3);
+    }
+}
@@ -0,0 +1,180 @@
+// `OwnedBuf` for `BorrowBuf`, so that generic code can take borrowed slices as well as owned
+// buffers.
+//
+// A bare `&mut [MaybeUninit<u8>]` has nowhere to keep the filled and initialized counts across
+// calls, so it cannot implement `OwnedBuf` itself; `BorrowBuf` is exactly that slice plus those
+// counts.
+
+use super::{FilledDrain, OwnedBuf, OwnedCursor};
+use crate::{BorrowBuf, BorrowCursor, CapacityError};
+use std::cmp;
+use std::mem::MaybeUninit;
+
+impl<'data> OwnedBuf for BorrowBuf<'data> {
+    type Cursor<'b>
+        = BorrowCursor<'data, 'b>
+    where
+        Self: 'b;
+    type Error = CapacityError;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        BorrowBuf::capacity(self)
+    }
+
+    fn len(&self) -> usize {
+        BorrowBuf::len(self)
+    }
+
+    fn init_len(&self) -> usize {
+        BorrowBuf::init_len(self)
+    }
+
+    fn filled(&self) -> &[u8] {
+        BorrowBuf::filled(self)
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        BorrowBuf::filled_mut(self)
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        BorrowBuf::unfilled(self)
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        BorrowCursor {
+            start: self.filled,
+            pos: self.filled,
+            end: self.initialized,
+            buf: self,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        BorrowBuf::clear(self)
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        BorrowBuf::set_init(self, n)
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), CapacityError> {
+        let available = self.remaining_capacity();
+        if additional > available {
+            return Err(CapacityError {
+                requested: additional,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), CapacityError> {
+        self.try_reserve(n)?;
+        self.filled += n;
+        self.initialized = cmp::max(self.initialized, self.filled);
+        Ok(())
+    }
+}
+
+impl<'data, 'a> OwnedCursor<'a> for BorrowCursor<'data, 'a> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(self.plone())
+    }
+
+    fn capacity(&self) -> usize {
+        BorrowCursor::capacity(self)
+    }
+
+    fn written(&self) -> usize {
+        BorrowCursor::written(self)
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        BorrowCursor::init_ref(self)
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        BorrowCursor::init_mut(self)
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        BorrowCursor::uninit_mut(self)
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        BorrowCursor::as_mut(self)
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let spare = unsafe { BorrowCursor::as_mut(self) };
+        (spare.as_mut_ptr() as *mut u8, spare.len())
+    }
+
+    unsafe fn advance(&mut self, n: usize) {
+        BorrowCursor::advance(self, n);
+    }
+
+    fn ensure_init(&mut self) {
+        BorrowCursor::ensure_init(self);
+    }
+
+    unsafe fn set_init(&mut self, n: usize) {
+        BorrowCursor::set_init(self, n);
+    }
+
+    // The buffer may be over an initialized `&mut [u8]`, and its initialized count outlives the
+    // cursor, so initialized bytes are never forgotten.
+    fn reset_init(&mut self, _n: usize) {}
+
+    fn append(&mut self, buf: &[u8]) {
+        BorrowCursor::append(self, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One routine for both owned and borrowed buffers.
+    fn fill_header<B: OwnedBuf>(buf: &mut B) -> usize {
+        buf.unfilled().append(&[0xCA, 0xFE]);
+        buf.len()
+    }
+
+    #[test]
+    fn generic_over_vec_and_slice() {
+        let mut v = Vec::with_capacity(8);
+        assert_eq!(fill_header(&mut v), 2);
+        assert_eq!(v, [0xCA, 0xFE]);
+
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        assert_eq!(fill_header(&mut buf), 2);
+        assert_eq!(OwnedBuf::filled(&buf), &[0xCA, 0xFE]);
+
+        let cursor = OwnedBuf::unfilled_init_only(&mut buf);
+        assert_eq!(OwnedCursor::capacity(&cursor), 0);
+        assert!(OwnedBuf::try_reserve(&mut buf, 7).is_err());
+    }
+
+    #[test]
+    fn reset_init_keeps_initialized() {
+        let mut bytes = [1; 8];
+        let mut buf = BorrowBuf::from(&mut bytes[..]);
+
+        let mut cursor = OwnedBuf::unfilled(&mut buf);
+        OwnedCursor::reset_init(&mut cursor, 0);
+        assert!(OwnedCursor::uninit_mut(&mut cursor).is_empty());
+        drop(cursor);
+        assert_eq!(buf.init_len(), 8);
+    }
+}
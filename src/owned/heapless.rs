@@ -0,0 +1,240 @@
+use super::{FilledDrain, OwnedBuf, OwnedCursor};
+use crate::{mem, CapacityError};
+use std::cmp;
+use std::mem::MaybeUninit;
+
+// The capacity of a `heapless::Vec` is fixed at `N`, so unlike `Vec<u8>` the buffer can never
+// grow; appending past `N` panics and reserving or advancing past `N` is an error. As for
+// `Vec<u8>`, the initialized count is not preserved between cursors.
+impl<const N: usize> OwnedBuf for ::heapless::Vec<u8, N> {
+    type Cursor<'b> = HeaplessCursor<'b, N>;
+    type Error = CapacityError;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        N
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn init_len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn filled(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        HeaplessCursor {
+            initialized: 0,
+            start: self.as_slice().len(),
+            end: N,
+            buf: self,
+        }
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        let len = self.as_slice().len();
+        HeaplessCursor {
+            initialized: 0,
+            start: len,
+            end: len,
+            buf: self,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        ::heapless::Vec::clear(self);
+        self
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        let len = self.as_slice().len();
+        self.set_len(cmp::max(len, n));
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), CapacityError> {
+        let available = N - self.as_slice().len();
+        if additional > available {
+            return Err(CapacityError {
+                requested: additional,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), CapacityError> {
+        self.try_reserve(n)?;
+        let len = self.as_slice().len();
+        self.set_len(len + n);
+        Ok(())
+    }
+}
+
+/// A cursor over the spare capacity of a `heapless::Vec<u8, N>`.
+pub struct HeaplessCursor<'a, const N: usize> {
+    buf: &'a mut ::heapless::Vec<u8, N>,
+    // relative to len of buf (not 0)
+    initialized: usize,
+    start: usize,
+    // absolute, at most N
+    end: usize,
+}
+
+impl<'a, const N: usize> HeaplessCursor<'a, N> {
+    // The part of the spare capacity covered by the cursor.
+    fn spare(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.buf.as_slice().len();
+        &mut self.buf.spare_capacity_mut()[..self.end - len]
+    }
+}
+
+impl<'a, const N: usize> OwnedCursor<'a> for HeaplessCursor<'a, N> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(HeaplessCursor {
+            buf: self.buf,
+            initialized: self.initialized,
+            start: self.start,
+            end: self.end,
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.end - self.buf.as_slice().len()
+    }
+
+    fn written(&self) -> usize {
+        self.buf.as_slice().len() - self.start
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.spare()[..initialized]) }
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.spare()[..initialized]) }
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let initialized = self.initialized;
+        &mut self.spare()[initialized..]
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.spare()
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let spare = self.spare();
+        (spare.as_mut_ptr() as *mut u8, spare.len())
+    }
+
+    #[track_caller]
+    unsafe fn advance(&mut self, n: usize) {
+        let len = self.buf.as_slice().len();
+        assert!(n <= self.end - len, "advance past the end of the cursor");
+        self.buf.set_len(len + n);
+        self.initialized = self.initialized.saturating_sub(n);
+    }
+
+    fn ensure_init(&mut self) {
+        mem::zero(self.uninit_mut());
+
+        self.initialized = self.capacity();
+    }
+
+    unsafe fn set_init(&mut self, n: usize) {
+        self.initialized = cmp::max(self.initialized, n);
+    }
+
+    fn reset_init(&mut self, n: usize) {
+        self.initialized = cmp::min(self.initialized, n);
+    }
+
+    #[track_caller]
+    fn append(&mut self, buf: &[u8]) {
+        let spare = self.spare();
+        assert!(
+            buf.len() <= spare.len(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            spare.len()
+        );
+        mem::copy(spare, buf);
+        unsafe {
+            // SAFETY we just wrote buf.len() bytes
+            self.advance(buf.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_to_capacity() {
+        let mut v = ::heapless::Vec::<u8, 8>::new();
+        let mut cursor = v.unfilled();
+        assert_eq!(cursor.capacity(), 8);
+
+        cursor.append(&[1, 2, 3]);
+        cursor.append(&[4, 5, 6, 7, 8]);
+        assert_eq!(cursor.capacity(), 0);
+        assert_eq!(cursor.written(), 8);
+        assert_eq!(OwnedBuf::filled(&v), &[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn try_reserve_advance() {
+        let mut v = ::heapless::Vec::<u8, 4>::new();
+        OwnedBuf::try_reserve(&mut v, 4).unwrap();
+        assert_eq!(
+            OwnedBuf::try_reserve(&mut v, 5),
+            Err(CapacityError {
+                requested: 5,
+                available: 4
+            })
+        );
+
+        v.unfilled().ensure_init();
+        unsafe {
+            v.try_advance(3).unwrap();
+            assert_eq!(
+                v.try_advance(2),
+                Err(CapacityError {
+                    requested: 2,
+                    available: 1
+                })
+            );
+        }
+        assert_eq!(OwnedBuf::filled(&v), &[0; 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot append 2 bytes, only 1 available")]
+    fn append_past_capacity() {
+        let mut v = ::heapless::Vec::<u8, 4>::new();
+        let mut cursor = v.unfilled();
+        cursor.append(&[1, 2, 3]);
+        cursor.append(&[4, 5]);
+    }
+}
@@ -0,0 +1,522 @@
+use super::{FilledDrain, OwnedBuf, OwnedCursor};
+use crate::{mem, CapacityError};
+use std::cmp;
+use std::convert::Infallible;
+use std::io::Cursor;
+use std::mem::MaybeUninit;
+use std::slice;
+
+fn position<T>(cursor: &Cursor<T>) -> usize {
+    usize::try_from(cursor.position()).expect("cursor position overflows usize")
+}
+
+// The filled part of the buffer is the part of the `Vec` before the cursor's position, so filling
+// the buffer overwrites the `Vec` from its position onwards, extending it as needed. Bytes of the
+// `Vec` after the position are treated as initialized but unfilled.
+//
+// If the position is past the end of the `Vec`, the buffer is only filled up to the end of the
+// `Vec`. Creating a cursor or advancing first pads the `Vec` with zeros up to the position, the
+// same as writing to a `Cursor<Vec<u8>>` does.
+impl OwnedBuf for Cursor<Vec<u8>> {
+    type Cursor<'b> = PositionedVecCursor<'b>;
+    type Error = Infallible;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        self.get_ref().capacity()
+    }
+
+    fn len(&self) -> usize {
+        cmp::min(position(self), self.get_ref().len())
+    }
+
+    fn init_len(&self) -> usize {
+        self.get_ref().len()
+    }
+
+    fn filled(&self) -> &[u8] {
+        &self.get_ref()[..OwnedBuf::len(self)]
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        let len = OwnedBuf::len(self);
+        &mut self.get_mut()[..len]
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        let pos = position(self);
+        if pos > self.get_ref().len() {
+            self.get_mut().resize(pos, 0);
+        }
+
+        PositionedVecCursor {
+            initialized: self.get_ref().len() - pos,
+            start: pos,
+            end: self.get_ref().capacity(),
+            buf: self,
+        }
+    }
+
+    // The elements of the `Vec` after the position are the initialized part of the cursor.
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        let mut cursor = self.unfilled();
+        cursor.end = cursor.buf.get_ref().len();
+        cursor
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.set_position(0);
+        self
+    }
+
+    // The bytes after the position are dropped.
+    fn into_filled_vec(self) -> Vec<u8> {
+        let pos = position(&self);
+        let mut vec = self.into_inner();
+        vec.truncate(pos);
+        vec
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        let len = self.get_ref().len();
+        self.get_mut().set_len(cmp::max(len, n));
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Infallible> {
+        let pos = position(self);
+        let vec = self.get_mut();
+        let end = pos.checked_add(additional).expect("capacity overflow");
+        vec.reserve(end.saturating_sub(vec.len()));
+        Ok(())
+    }
+
+    #[track_caller]
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), Infallible> {
+        let pos = position(self);
+        // The position may be past the end of the allocation.
+        assert!(
+            n <= self.get_ref().capacity().saturating_sub(pos),
+            "advance past the capacity of the Vec"
+        );
+        let vec = self.get_mut();
+        if pos > vec.len() {
+            vec.resize(pos, 0);
+        }
+        vec.set_len(cmp::max(vec.len(), pos + n));
+        self.set_position((pos + n) as u64);
+        Ok(())
+    }
+}
+
+/// A cursor over a `std::io::Cursor<Vec<u8>>`, starting at the `Cursor`'s position.
+pub struct PositionedVecCursor<'a> {
+    buf: &'a mut Cursor<Vec<u8>>,
+    // relative to the position of buf (not 0)
+    initialized: usize,
+    start: usize,
+    // absolute, at most the capacity of the `Vec`
+    end: usize,
+}
+
+impl<'a> PositionedVecCursor<'a> {
+    // The `Vec`'s allocation from the position up to the end of the cursor, including any of the
+    // `Vec`'s elements.
+    fn tail(&mut self) -> &mut [MaybeUninit<u8>] {
+        let pos = position(self.buf);
+        let vec = self.buf.get_mut();
+        // SAFETY: the allocation is valid for `capacity` bytes and `pos <= end <= capacity`. The
+        // elements of the `Vec` stay initialized because the methods which expose this slice never
+        // de-initialize the initialized part of the cursor.
+        unsafe {
+            slice::from_raw_parts_mut(
+                vec.as_mut_ptr().add(pos) as *mut MaybeUninit<u8>,
+                self.end - pos,
+            )
+        }
+    }
+}
+
+impl<'a> OwnedCursor<'a> for PositionedVecCursor<'a> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(PositionedVecCursor {
+            buf: self.buf,
+            initialized: self.initialized,
+            start: self.start,
+            end: self.end,
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.end - position(self.buf)
+    }
+
+    fn written(&self) -> usize {
+        position(self.buf) - self.start
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.tail()[..initialized]) }
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        let initialized = self.initialized;
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.tail()[..initialized]) }
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let initialized = self.initialized;
+        &mut self.tail()[initialized..]
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.tail()
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let tail = self.tail();
+        (tail.as_mut_ptr() as *mut u8, tail.len())
+    }
+
+    #[track_caller]
+    unsafe fn advance(&mut self, n: usize) {
+        let pos = position(self.buf);
+        assert!(n <= self.capacity(), "advance past the end of the cursor");
+        let vec = self.buf.get_mut();
+        vec.set_len(cmp::max(vec.len(), pos + n));
+        self.buf.set_position((pos + n) as u64);
+        self.initialized = self.initialized.saturating_sub(n);
+    }
+
+    fn ensure_init(&mut self) {
+        mem::zero(self.uninit_mut());
+
+        self.initialized = self.capacity();
+    }
+
+    unsafe fn set_init(&mut self, n: usize) {
+        self.initialized = cmp::max(self.initialized, n);
+    }
+
+    // The elements of the `Vec` after the position are never forgotten.
+    fn reset_init(&mut self, n: usize) {
+        let elements = self.buf.get_ref().len() - position(self.buf);
+        self.initialized = cmp::min(self.initialized, cmp::max(n, elements));
+    }
+
+    #[track_caller]
+    fn append(&mut self, buf: &[u8]) {
+        let tail = self.tail();
+        assert!(
+            buf.len() <= tail.len(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            tail.len()
+        );
+        mem::copy(tail, buf);
+        unsafe {
+            // SAFETY we just wrote buf.len() bytes
+            self.set_init(buf.len());
+            self.advance(buf.len());
+        }
+    }
+}
+
+// The filled part of the buffer is the part of the slice before the cursor's position, so filling
+// the buffer overwrites the slice from its position onwards. The whole slice is initialized and it
+// can never grow, so reserving or advancing past the end of the slice is an error. If the position
+// is past the end of the slice, the buffer is full.
+impl<'s> OwnedBuf for Cursor<&'s mut [u8]> {
+    type Cursor<'b>
+        = PositionedSliceCursor<'b, 's>
+    where
+        Self: 'b;
+    type Error = CapacityError;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        self.get_ref().len()
+    }
+
+    fn len(&self) -> usize {
+        cmp::min(position(self), self.get_ref().len())
+    }
+
+    fn init_len(&self) -> usize {
+        self.get_ref().len()
+    }
+
+    fn filled(&self) -> &[u8] {
+        &self.get_ref()[..OwnedBuf::len(self)]
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        let len = OwnedBuf::len(self);
+        &mut self.get_mut()[..len]
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        PositionedSliceCursor {
+            start: OwnedBuf::len(self),
+            buf: self,
+        }
+    }
+
+    // The whole slice is initialized.
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        self.unfilled()
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.set_position(0);
+        self
+    }
+
+    unsafe fn set_init(&mut self, _n: usize) -> &mut Self {
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), CapacityError> {
+        let available = self.get_ref().len() - OwnedBuf::len(self);
+        if additional > available {
+            return Err(CapacityError {
+                requested: additional,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), CapacityError> {
+        self.try_reserve(n)?;
+        let len = OwnedBuf::len(self);
+        self.set_position((len + n) as u64);
+        Ok(())
+    }
+}
+
+/// A cursor over a `std::io::Cursor<&mut [u8]>`, starting at the `Cursor`'s position.
+///
+/// Since the slice is initialized, the whole cursor is always initialized.
+pub struct PositionedSliceCursor<'a, 's> {
+    buf: &'a mut Cursor<&'s mut [u8]>,
+    start: usize,
+}
+
+impl<'a, 's> PositionedSliceCursor<'a, 's> {
+    // The part of the slice after the position.
+    fn tail(&mut self) -> &mut [u8] {
+        let pos = OwnedBuf::len(self.buf);
+        &mut self.buf.get_mut()[pos..]
+    }
+}
+
+impl<'a, 's> OwnedCursor<'a> for PositionedSliceCursor<'a, 's> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(PositionedSliceCursor {
+            buf: self.buf,
+            start: self.start,
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.buf.get_ref().len() - OwnedBuf::len(self.buf)
+    }
+
+    fn written(&self) -> usize {
+        OwnedBuf::len(self.buf) - self.start
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        self.tail()
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        self.tail()
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        &mut []
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        (self.tail() as *mut [u8]).as_uninit_slice_mut().unwrap()
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let tail = self.tail();
+        (tail.as_mut_ptr(), tail.len())
+    }
+
+    #[track_caller]
+    unsafe fn advance(&mut self, n: usize) {
+        assert!(n <= self.capacity(), "advance past the end of the cursor");
+        let pos = OwnedBuf::len(self.buf);
+        self.buf.set_position((pos + n) as u64);
+    }
+
+    fn ensure_init(&mut self) {}
+
+    unsafe fn set_init(&mut self, _n: usize) {}
+
+    // The slice is always initialized, so there is nothing to forget.
+    fn reset_init(&mut self, _n: usize) {}
+
+    #[track_caller]
+    fn append(&mut self, buf: &[u8]) {
+        let tail = self.tail();
+        assert!(
+            buf.len() <= tail.len(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            tail.len()
+        );
+        tail[..buf.len()].copy_from_slice(buf);
+        unsafe {
+            // SAFETY we just wrote buf.len() bytes
+            self.advance(buf.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_from_position() {
+        let mut vec = Vec::with_capacity(8);
+        vec.extend_from_slice(&[1, 2, 3, 4, 5]);
+        let mut c = Cursor::new(vec);
+        c.set_position(2);
+
+        assert_eq!(OwnedBuf::filled(&c), &[1, 2]);
+        assert_eq!(c.init_len(), 5);
+
+        let mut cursor = c.unfilled();
+        assert_eq!(cursor.capacity(), 6);
+        assert_eq!(cursor.init_ref(), &[3, 4, 5]);
+        cursor.append(&[9, 9]);
+        assert_eq!(cursor.init_ref(), &[5]);
+        cursor.append(&[8, 8]);
+        assert_eq!(cursor.written(), 4);
+
+        assert_eq!(c.position(), 6);
+        assert_eq!(c.get_ref(), &[1, 2, 9, 9, 8, 8]);
+        assert_eq!(OwnedBuf::filled(&c), &[1, 2, 9, 9, 8, 8]);
+    }
+
+    #[test]
+    fn unfilled_init_only() {
+        let mut vec = Vec::with_capacity(8);
+        vec.extend_from_slice(&[1, 2, 3, 4, 5]);
+        let mut c = Cursor::new(vec);
+        c.set_position(2);
+
+        let mut cursor = c.unfilled_init_only();
+        assert_eq!(cursor.capacity(), 3);
+        assert!(cursor.uninit_mut().is_empty());
+        assert_eq!(cursor.read_from(&mut &[7; 8][..]).unwrap(), 3);
+        assert_eq!(c.get_ref(), &[1, 2, 7, 7, 7]);
+    }
+
+    #[test]
+    fn reset_init_keeps_elements() {
+        let mut vec = Vec::with_capacity(8);
+        vec.extend_from_slice(&[1, 2, 3, 4]);
+        let mut c = Cursor::new(vec);
+        c.set_position(1);
+
+        let mut cursor = c.unfilled();
+        cursor.ensure_init();
+        cursor.reset_init(0);
+        assert_eq!(cursor.init_ref(), &[2, 3, 4]);
+        assert_eq!(cursor.uninit_mut().len(), 4);
+    }
+
+    #[test]
+    fn fill_slice_from_position() {
+        let mut slice = [1, 2, 3, 4, 5, 6];
+        let mut c = Cursor::new(&mut slice[..]);
+        c.set_position(2);
+
+        assert_eq!(OwnedBuf::filled(&c), &[1, 2]);
+        assert_eq!(c.init_len(), 6);
+
+        let mut cursor = c.unfilled();
+        assert_eq!(cursor.capacity(), 4);
+        assert_eq!(cursor.init_ref(), &[3, 4, 5, 6]);
+        cursor.append(&[9, 9]);
+        assert_eq!(cursor.read_from(&mut &[8; 4][..]).unwrap(), 2);
+        assert!(cursor.is_full());
+        assert_eq!(cursor.written(), 4);
+
+        assert_eq!(c.position(), 6);
+        assert_eq!(OwnedBuf::filled(&c), &[1, 2, 9, 9, 8, 8]);
+        assert_eq!(
+            OwnedBuf::try_reserve(&mut c, 1),
+            Err(CapacityError {
+                requested: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot append 3 bytes, only 2 available")]
+    fn append_past_end_of_slice() {
+        let mut slice = [0; 4];
+        let mut c = Cursor::new(&mut slice[..]);
+        c.set_position(2);
+        c.unfilled().append(&[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_at_huge_position() {
+        let mut c = Cursor::new(Vec::new());
+        c.set_position(usize::MAX as u64 - 1);
+        let _ = OwnedBuf::try_reserve(&mut c, 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn advance_at_huge_position() {
+        let mut c = Cursor::new(Vec::with_capacity(8));
+        c.set_position(usize::MAX as u64 - 1);
+        unsafe {
+            let _ = c.try_advance(4);
+        }
+    }
+
+    #[test]
+    fn position_past_end() {
+        let mut c = Cursor::new(vec![1, 2]);
+        c.set_position(4);
+
+        assert_eq!(OwnedBuf::len(&c), 2);
+        assert_eq!(OwnedBuf::filled(&c), &[1, 2]);
+        assert_eq!(c.init_len(), 2);
+        assert_eq!(c.drain_filled().len(), 2);
+        c.set_position(4);
+
+        c.unfilled().append(&[3]);
+        assert_eq!(c.get_ref(), &[1, 2, 0, 0, 3]);
+        assert_eq!(c.position(), 5);
+    }
+}
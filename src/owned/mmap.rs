@@ -0,0 +1,161 @@
+use super::{ArenaBuf, ArenaCursor, FilledDrain, OwnedBuf};
+use crate::CapacityError;
+use memmap2::MmapMut;
+use std::io;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+/// An owned buffer over a writable memory map, e.g., for reading from a socket straight into a
+/// file.
+///
+/// The whole map is initialized, since it is backed by the file, so reading into it never needs
+/// zeroing. The buffer cannot grow past the end of the map. Data written into the map is not
+/// guaranteed to reach the file until it is flushed, with `flush` or by dropping the map.
+pub struct MmapBuf {
+    inner: ArenaBuf<MmapStorage>,
+}
+
+// The memory of an `MmapBuf`. This is private, since writing uninitialized bytes through its
+// `DerefMut` impl and then reading the map would be undefined behaviour.
+struct MmapStorage {
+    map: MmapMut,
+}
+
+impl Deref for MmapStorage {
+    type Target = [MaybeUninit<u8>];
+
+    fn deref(&self) -> &[MaybeUninit<u8>] {
+        let bytes: &[u8] = &self.map;
+        //SAFETY: `MaybeUninit<u8>` has the same layout as `u8`
+        unsafe { &*(bytes as *const [u8] as *const [MaybeUninit<u8>]) }
+    }
+}
+
+impl DerefMut for MmapStorage {
+    fn deref_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        //SAFETY: the map is always initialized, and the buffer never de-initializes initialized
+        // bytes
+        unsafe { (&mut *self.map as *mut [u8]).as_uninit_slice_mut().unwrap() }
+    }
+}
+
+impl MmapBuf {
+    /// Creates a new, empty buffer over the whole of `map`.
+    pub fn from_mmap(map: MmapMut) -> MmapBuf {
+        let len = map.len();
+        // SAFETY: a memory map always derefs to the same slice, and owns its memory. The whole map
+        // is initialized.
+        let inner = unsafe {
+            let mut inner = ArenaBuf::new(MmapStorage { map });
+            inner.set_init(len);
+            inner.pin_init();
+            inner
+        };
+        MmapBuf { inner }
+    }
+
+    /// Flushes the filled part of the map to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner.get_ref().map.flush_range(0, self.len())
+    }
+
+    /// Returns the memory map.
+    pub fn into_map(self) -> MmapMut {
+        self.inner.into_inner().map
+    }
+}
+
+impl OwnedBuf for MmapBuf {
+    type Cursor<'b> = ArenaCursor<'b>;
+    type Error = CapacityError;
+    type Drain<'d> = FilledDrain<'d, Self>;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn init_len(&self) -> usize {
+        self.inner.init_len()
+    }
+
+    fn filled(&self) -> &[u8] {
+        self.inner.filled()
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        self.inner.filled_mut()
+    }
+
+    fn unfilled<'b>(&'b mut self) -> ArenaCursor<'b> {
+        self.inner.unfilled()
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> ArenaCursor<'b> {
+        self.inner.unfilled_init_only()
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> FilledDrain<'d, Self> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.inner.clear();
+        self
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        self.inner.set_init(n);
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), CapacityError> {
+        self.inner.try_reserve(additional)
+    }
+
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), CapacityError> {
+        self.inner.try_advance(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::owned::OwnedCursor;
+    use std::fs::{self, OpenOptions};
+    use std::process;
+
+    #[test]
+    fn fill_mapped_file() {
+        let path = std::env::temp_dir().join(format!("read-buf-mmap-{}", process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.set_len(8).unwrap();
+
+        // SAFETY: the file is not modified by anything else while it is mapped.
+        let map = unsafe { MmapMut::map_mut(&file) }.unwrap();
+        let mut buf = MmapBuf::from_mmap(map);
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(buf.init_len(), 8);
+
+        let mut cursor = buf.unfilled();
+        cursor.reset_init(0);
+        assert!(cursor.uninit_mut().is_empty());
+        cursor.read_from(&mut &b"mapped"[..]).unwrap();
+        assert_eq!(buf.filled(), b"mapped");
+        assert!(buf.try_reserve(3).is_err());
+        buf.flush().unwrap();
+        assert_eq!(&buf.into_map()[..], b"mapped\0\0");
+
+        assert_eq!(fs::read(&path).unwrap(), b"mapped\0\0");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+use super::{grow_if_full, read_into_init, FilledDrain, OwnedBuf, OwnedCursor};
+use crate::mem;
+use std::cmp;
+use std::convert::Infallible;
+use std::io::{self, Read};
+use std::mem::MaybeUninit;
+
+/// A `Vec<u8>` which remembers how much of its spare capacity is initialized.
+///
+/// `Vec<u8>` itself does not store an initialized count, so every cursor created from a `Vec` has
+/// to initialize its spare capacity again. `VecBuf` keeps the count between cursors, so bytes
+/// initialized via one cursor (e.g., by `ensure_init` before a short read) are still known to be
+/// initialized by the next.
+///
+/// If the `Vec` is reallocated, its spare capacity is not preserved and the count is reset to the
+/// length of the `Vec`.
+#[derive(Debug, Default)]
+pub struct VecBuf {
+    vec: Vec<u8>,
+    // absolute, `vec.len() <= initialized <= vec.capacity()`
+    initialized: usize,
+}
+
+impl VecBuf {
+    /// Creates a new `VecBuf` from a `Vec`, whose spare capacity is assumed to be uninitialized.
+    pub fn new(vec: Vec<u8>) -> VecBuf {
+        VecBuf {
+            initialized: vec.len(),
+            vec,
+        }
+    }
+
+    /// Creates a new, empty `VecBuf` with at least the given capacity.
+    pub fn with_capacity(capacity: usize) -> VecBuf {
+        VecBuf::new(Vec::with_capacity(capacity))
+    }
+
+    /// Returns the underlying `Vec`, forgetting the initialized count.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.vec
+    }
+
+    // Reserves space in the `Vec`, forgetting the initialized count if it is reallocated.
+    fn reserve(&mut self, additional: usize) {
+        let capacity = self.vec.capacity();
+        self.vec.reserve(additional);
+        if self.vec.capacity() != capacity {
+            self.initialized = self.vec.len();
+        }
+    }
+}
+
+impl OwnedBuf for VecBuf {
+    type Cursor<'b> = VecBufCursor<'b>;
+    type Error = Infallible;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    fn init_len(&self) -> usize {
+        self.initialized
+    }
+
+    fn filled(&self) -> &[u8] {
+        &self.vec
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        &mut self.vec
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        VecBufCursor {
+            start: self.vec.len(),
+            end: self.vec.capacity(),
+            vec: &mut self.vec,
+            initialized: &mut self.initialized,
+        }
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        VecBufCursor {
+            start: self.vec.len(),
+            end: self.initialized,
+            vec: &mut self.vec,
+            initialized: &mut self.initialized,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.vec.clear();
+        self
+    }
+
+    fn into_filled_vec(self) -> Vec<u8> {
+        self.vec
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        self.initialized = cmp::max(self.initialized, n);
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Infallible> {
+        self.reserve(additional);
+        Ok(())
+    }
+
+    // As for `Vec`, advancing past the capacity is a bug in the caller and panics.
+    #[track_caller]
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), Infallible> {
+        let len = self.vec.len();
+        assert!(
+            n <= self.vec.capacity() - len,
+            "advance past the capacity of the Vec"
+        );
+        self.vec.set_len(len + n);
+        self.initialized = cmp::max(self.initialized, len + n);
+        Ok(())
+    }
+}
+
+/// A buffer over the spare capacity of a borrowed `Vec<u8>`, which can grow the `Vec`.
+///
+/// This is like a `BorrowBuf` over `vec.spare_capacity_mut()`, except that it holds the `Vec`
+/// itself rather than a detached slice, so `try_grow` can make more room mid-read. Filled bytes
+/// are appended to the `Vec` (they start after its existing contents, which are not part of the
+/// buffer), so they survive a reallocation. As for `VecBuf`, the initialized count is reset if the
+/// `Vec` is reallocated.
+#[derive(Debug)]
+pub struct VecSpareBuf<'v> {
+    vec: &'v mut Vec<u8>,
+    // The length of the `Vec` when the buffer was created.
+    start: usize,
+    // absolute, `vec.len() <= initialized <= vec.capacity()`
+    initialized: usize,
+}
+
+impl<'v> VecSpareBuf<'v> {
+    /// Creates a new, empty buffer over the spare capacity of `vec`.
+    pub fn new(vec: &'v mut Vec<u8>) -> VecSpareBuf<'v> {
+        VecSpareBuf {
+            start: vec.len(),
+            initialized: vec.len(),
+            vec,
+        }
+    }
+
+    /// Tries to reserve space for at least `additional` more bytes in the `Vec`.
+    ///
+    /// Returns `false`, leaving the buffer unchanged, if the allocation fails. The filled bytes are
+    /// preserved, but if the `Vec` is reallocated, the initialized count is reset to the filled
+    /// length.
+    pub fn try_grow(&mut self, additional: usize) -> bool {
+        let capacity = self.vec.capacity();
+        if self.vec.try_reserve(additional).is_err() {
+            return false;
+        }
+        if self.vec.capacity() != capacity {
+            self.initialized = self.vec.len();
+        }
+        true
+    }
+}
+
+impl<'v> From<&'v mut Vec<u8>> for VecSpareBuf<'v> {
+    fn from(vec: &'v mut Vec<u8>) -> VecSpareBuf<'v> {
+        VecSpareBuf::new(vec)
+    }
+}
+
+impl<'v> OwnedBuf for VecSpareBuf<'v> {
+    type Cursor<'b>
+        = VecBufCursor<'b>
+    where
+        Self: 'b;
+    type Error = Infallible;
+    type Drain<'d>
+        = FilledDrain<'d, Self>
+    where
+        Self: 'd;
+
+    fn capacity(&self) -> usize {
+        self.vec.capacity() - self.start
+    }
+
+    fn len(&self) -> usize {
+        self.vec.len() - self.start
+    }
+
+    fn init_len(&self) -> usize {
+        self.initialized - self.start
+    }
+
+    fn filled(&self) -> &[u8] {
+        &self.vec[self.start..]
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        &mut self.vec[self.start..]
+    }
+
+    fn unfilled<'b>(&'b mut self) -> Self::Cursor<'b> {
+        VecBufCursor {
+            start: self.vec.len(),
+            end: self.vec.capacity(),
+            vec: self.vec,
+            initialized: &mut self.initialized,
+        }
+    }
+
+    fn unfilled_init_only<'b>(&'b mut self) -> Self::Cursor<'b> {
+        VecBufCursor {
+            start: self.vec.len(),
+            end: self.initialized,
+            vec: self.vec,
+            initialized: &mut self.initialized,
+        }
+    }
+
+    fn drain_filled<'d>(&'d mut self) -> Self::Drain<'d> {
+        FilledDrain::new(self)
+    }
+
+    fn clear(&mut self) -> &mut Self {
+        self.vec.truncate(self.start);
+        self
+    }
+
+    unsafe fn set_init(&mut self, n: usize) -> &mut Self {
+        self.initialized = cmp::max(self.initialized, self.start + n);
+        self
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), Infallible> {
+        let capacity = self.vec.capacity();
+        self.vec.reserve(additional);
+        if self.vec.capacity() != capacity {
+            self.initialized = self.vec.len();
+        }
+        Ok(())
+    }
+
+    #[track_caller]
+    unsafe fn try_advance(&mut self, n: usize) -> Result<(), Infallible> {
+        let len = self.vec.len();
+        assert!(
+            n <= self.vec.capacity() - len,
+            "advance past the capacity of the Vec"
+        );
+        self.vec.set_len(len + n);
+        self.initialized = cmp::max(self.initialized, len + n);
+        Ok(())
+    }
+}
+
+/// A cursor over the spare capacity of a [`VecBuf`] or [`VecSpareBuf`].
+pub struct VecBufCursor<'a> {
+    vec: &'a mut Vec<u8>,
+    // absolute, shared with the `VecBuf`
+    initialized: &'a mut usize,
+    start: usize,
+    // absolute, at most the capacity of vec
+    end: usize,
+}
+
+impl<'a> VecBufCursor<'a> {
+    // The part of the spare capacity covered by the cursor.
+    fn spare(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.vec.len();
+        &mut self.vec.spare_capacity_mut()[..self.end - len]
+    }
+
+    // The number of initialized bytes in the cursor.
+    fn init_len(&self) -> usize {
+        cmp::min(*self.initialized, self.end) - self.vec.len()
+    }
+}
+
+impl<'a> OwnedCursor<'a> for VecBufCursor<'a> {
+    fn clone<'c>(&'c mut self) -> Box<dyn OwnedCursor<'c> + 'c> {
+        Box::new(VecBufCursor {
+            vec: self.vec,
+            initialized: self.initialized,
+            start: self.start,
+            end: self.end,
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.end - self.vec.len()
+    }
+
+    fn written(&self) -> usize {
+        self.vec.len() - self.start
+    }
+
+    fn init_ref(&mut self) -> &[u8] {
+        let init_len = self.init_len();
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.spare()[..init_len]) }
+    }
+
+    fn init_mut(&mut self) -> &mut [u8] {
+        let init_len = self.init_len();
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.spare()[..init_len]) }
+    }
+
+    fn uninit_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let init_len = self.init_len();
+        &mut self.spare()[init_len..]
+    }
+
+    unsafe fn as_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.spare()
+    }
+
+    fn spare_ptr_len(&mut self) -> (*mut u8, usize) {
+        let spare = self.spare();
+        (spare.as_mut_ptr() as *mut u8, spare.len())
+    }
+
+    #[track_caller]
+    unsafe fn advance(&mut self, n: usize) {
+        let len = self.vec.len();
+        assert!(n <= self.end - len, "advance past the end of the cursor");
+        self.vec.set_len(len + n);
+        *self.initialized = cmp::max(*self.initialized, len + n);
+    }
+
+    fn ensure_init(&mut self) {
+        mem::zero(self.uninit_mut());
+
+        *self.initialized = cmp::max(*self.initialized, self.end);
+    }
+
+    unsafe fn set_init(&mut self, n: usize) {
+        *self.initialized = cmp::max(*self.initialized, self.vec.len() + n);
+    }
+
+    fn reset_init(&mut self, n: usize) {
+        *self.initialized = cmp::min(*self.initialized, self.vec.len() + n);
+    }
+
+    #[track_caller]
+    fn append(&mut self, buf: &[u8]) {
+        let spare = self.spare();
+        assert!(
+            buf.len() <= spare.len(),
+            "cannot append {} bytes, only {} available",
+            buf.len(),
+            spare.len()
+        );
+        mem::copy(spare, buf);
+        unsafe {
+            // SAFETY we just wrote buf.len() bytes
+            self.advance(buf.len());
+        }
+    }
+
+    // Grows the `Vec` if it is full, see `grow_if_full`.
+    fn read_from<R: Read + ?Sized>(&mut self, r: &mut R) -> io::Result<usize> {
+        if grow_if_full(self.vec, &mut self.end) {
+            *self.initialized = self.vec.len();
+        }
+        read_into_init(self, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_shared_between_cursors() {
+        let mut buf = VecBuf::with_capacity(16);
+        let capacity = buf.capacity();
+
+        let mut cursor = buf.unfilled();
+        cursor.ensure_init();
+        cursor.init_mut().fill(7);
+        cursor.append(&[1, 2]);
+        assert_eq!(buf.init_len(), capacity);
+
+        // The next cursor sees the bytes written by the first, rather than zeroing them again.
+        let mut cursor = buf.unfilled();
+        assert!(cursor.uninit_mut().is_empty());
+        assert!(cursor.init_ref().iter().all(|&b| b == 7));
+        assert_eq!(cursor.init_ref().len(), capacity - 2);
+
+        let mut cursor = buf.unfilled_init_only();
+        assert_eq!(cursor.capacity(), capacity - 2);
+        assert_eq!(cursor.read_from(&mut &[3, 4][..]).unwrap(), 2);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+
+        buf.clear();
+        assert_eq!(buf.init_len(), capacity);
+        assert_eq!(buf.into_inner().capacity(), capacity);
+    }
+
+    #[test]
+    fn spare_buf_grows_mid_fill() {
+        let mut vec = vec![9];
+        vec.shrink_to_fit();
+        let mut buf = VecSpareBuf::from(&mut vec);
+        assert_eq!(buf.capacity(), 0);
+
+        assert!(buf.try_grow(2));
+        buf.unfilled().append(&[1, 2]);
+        assert!(buf.try_grow(64));
+        assert_eq!(buf.filled(), &[1, 2]);
+        assert_eq!(buf.init_len(), 2);
+
+        buf.unfilled().append(&[3]);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+        assert!(!buf.try_grow(usize::MAX));
+
+        buf.clear();
+        assert_eq!(buf.len(), 0);
+        buf.unfilled().append(&[4]);
+        assert_eq!(vec, [9, 4]);
+    }
+
+    #[test]
+    fn reallocation_forgets_init() {
+        let mut buf = VecBuf::with_capacity(4);
+        buf.unfilled().ensure_init();
+        buf.unfilled().append(&[1]);
+
+        OwnedBuf::try_reserve(&mut buf, 64).unwrap();
+        assert_eq!(buf.init_len(), 1);
+        assert_eq!(buf.unfilled().init_ref().len(), 0);
+    }
+}
@@ -0,0 +1,334 @@
+//! Helpers for filling buffers from readers.
+
+use crate::{BorrowBuf, BorrowCursor};
+use std::cmp;
+use std::io::{self, ErrorKind, IoSliceMut, Read};
+use std::time::Instant;
+
+/// Reads from `r` into the cursor once, advancing it by the number of bytes read.
+///
+/// The whole cursor is initialized before reading, since `Read::read` requires an initialized
+/// buffer. Returns the number of bytes read; as for `Read::read`, zero means the end of the input
+/// (or that the cursor is full).
+pub fn read_buf<R: Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+) -> io::Result<usize> {
+    if cursor.is_full() {
+        return Ok(0);
+    }
+
+    let buf = cursor.ensure_init_mut();
+    let len = buf.len();
+    let n = r.read(buf)?;
+    assert!(
+        n <= len,
+        "reader returned more bytes than the buffer can hold"
+    );
+    unsafe {
+        // SAFETY: the whole cursor was initialized above
+        cursor.advance(n);
+    }
+    Ok(n)
+}
+
+/// Extends `Read` with reading into a [`BorrowCursor`](crate::BorrowCursor).
+///
+/// This is implemented for unsized readers too, so it can be used through `&mut dyn Read`.
+pub trait ReadBuf: Read {
+    /// Reads into the cursor once, see [`read_buf`].
+    fn read_buf(&mut self, cursor: &mut BorrowCursor<'_, '_>) -> io::Result<usize> {
+        read_buf(self, cursor)
+    }
+
+    /// Reads until the cursor is full, see [`read_buf_exact`].
+    fn read_buf_exact(&mut self, cursor: &mut BorrowCursor<'_, '_>) -> io::Result<()> {
+        read_buf_exact(self, cursor)
+    }
+}
+
+impl<R: Read + ?Sized> ReadBuf for R {}
+
+/// Reads from `r` once into up to `additional` bytes of spare capacity of `vec`.
+///
+/// Reserves `additional` bytes, reads into the spare capacity through a `BorrowBuf` as for
+/// `read_buf`, and extends the length of `vec` by the number of bytes read, which is returned.
+pub fn fill_vec_from<R: Read + ?Sized>(
+    vec: &mut Vec<u8>,
+    additional: usize,
+    r: &mut R,
+) -> io::Result<usize> {
+    vec.reserve(additional);
+    let mut buf = BorrowBuf::from(&mut vec.spare_capacity_mut()[..additional]);
+    let n = read_buf(r, &mut buf.unfilled())?;
+    // SAFETY: the first `n` bytes of the spare capacity were filled by the read
+    unsafe {
+        vec.set_len(vec.len() + n);
+    }
+    Ok(n)
+}
+
+/// Reads from `r` into several cursors with a single vectored read.
+///
+/// Each cursor is fully initialized and passed to `Read::read_vectored` as one `IoSliceMut`. The
+/// number of bytes read is then shared out over the cursors in order, filling each cursor before
+/// advancing the next. Returns the total number of bytes read.
+pub fn read_buf_vectored<R: Read + ?Sized>(
+    r: &mut R,
+    cursors: &mut [BorrowCursor<'_, '_>],
+) -> io::Result<usize> {
+    let n = {
+        let mut slices: Vec<IoSliceMut<'_>> = cursors
+            .iter_mut()
+            .map(|cursor| {
+                cursor.ensure_init();
+                IoSliceMut::new(cursor.init_mut())
+            })
+            .collect();
+        r.read_vectored(&mut slices)?
+    };
+
+    let mut remaining = n;
+    for cursor in cursors {
+        let advance = cmp::min(remaining, cursor.capacity());
+        // SAFETY: the whole cursor was initialized above
+        unsafe {
+            cursor.advance(advance);
+        }
+        remaining -= advance;
+    }
+    assert_eq!(
+        remaining, 0,
+        "reader returned more bytes than the buffers can hold"
+    );
+
+    Ok(n)
+}
+
+// Reads from `r` into the cursor until it is full, the reader reaches the end of its input, or
+// `on_read` returns `false`.
+//
+// `on_read` is called after each read with the number of bytes read by this call so far. Reads
+// which fail with `ErrorKind::Interrupted` are retried. Returns the number of bytes read; if
+// another error occurs, it is returned instead, and the bytes read before it remain filled in the
+// cursor.
+fn read_while<R: Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+    mut on_read: impl FnMut(usize) -> bool,
+) -> io::Result<usize> {
+    let start = cursor.written();
+    cursor.ensure_init();
+    while !cursor.is_full() {
+        match r.read(cursor.init_mut()) {
+            Ok(0) => break,
+            Ok(n) => {
+                assert!(
+                    n <= cursor.capacity(),
+                    "reader returned more bytes than the buffer can hold"
+                );
+                // SAFETY: the whole cursor was initialized above
+                unsafe {
+                    cursor.advance(n);
+                }
+                if !on_read(cursor.written() - start) {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(cursor.written() - start)
+}
+
+/// Reads from `r` into the cursor until it is full, the reader reaches the end of its input, or
+/// `deadline` passes.
+///
+/// The deadline is checked before each read, so a read which blocks is not interrupted; this is
+/// intended for non-blocking readers or readers with a timeout. Reads which fail with
+/// `ErrorKind::Interrupted` are retried. Returns the number of bytes read; if another error
+/// occurs, it is returned instead, and the bytes read before it remain filled in the cursor.
+pub fn read_buf_until<R: Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+    deadline: Instant,
+) -> io::Result<usize> {
+    if Instant::now() >= deadline {
+        return Ok(0);
+    }
+    read_while(r, cursor, |_| Instant::now() < deadline)
+}
+
+/// Reads from `r` into the cursor until it is full or the reader reaches the end of its input,
+/// calling `progress` after each read.
+///
+/// `progress` is passed the total number of bytes read by this call so far, so it can be used to
+/// update a progress indicator during large reads. Reads which fail with `ErrorKind::Interrupted`
+/// are retried. Returns the number of bytes read; if another error occurs, it is returned instead,
+/// and the bytes read before it remain filled in the cursor.
+pub fn read_buf_with_progress<R: Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+    mut progress: impl FnMut(usize),
+) -> io::Result<usize> {
+    read_while(r, cursor, |total| {
+        progress(total);
+        true
+    })
+}
+
+/// Reads from `r` until the cursor is full.
+///
+/// Reads which fail with `ErrorKind::Interrupted` are retried. If the reader reaches the end of its
+/// input first, an error of kind `ErrorKind::UnexpectedEof` is returned; the bytes read before
+/// that, or before any other error, remain filled in the cursor.
+pub fn read_buf_exact<R: Read + ?Sized>(
+    r: &mut R,
+    cursor: &mut BorrowCursor<'_, '_>,
+) -> io::Result<()> {
+    read_while(r, cursor, |_| true)?;
+    if !cursor.is_full() {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "failed to fill whole buffer",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads exactly `N` bytes from `r` into an array.
+///
+/// This is intended for fixed-width headers. Errors are as for `read_buf_exact`; on error, the
+/// bytes which were read are lost.
+pub fn read_array<const N: usize, R: Read + ?Sized>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut array = [0; N];
+    read_buf_exact(r, &mut BorrowBuf::from(&mut array[..]).unfilled())?;
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+    use std::thread;
+    use std::time::Duration;
+
+    // Yields one byte per read, slowly, forever.
+    struct Slow;
+
+    impl Read for Slow {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(Duration::from_millis(2));
+            buf[0] = 1;
+            Ok(1)
+        }
+    }
+
+    // Reads at most 4 bytes at a time from the slice.
+    struct Chunked<'r>(&'r [u8]);
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = cmp::min(buf.len(), 4);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn read_until_deadline() {
+        let mut backing = [MaybeUninit::uninit(); 1024];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let deadline = Instant::now() + Duration::from_millis(20);
+        let n = read_buf_until(&mut Slow, &mut buf.unfilled(), deadline).unwrap();
+        assert!(Instant::now() >= deadline);
+        assert!(n > 0 && n < 1024);
+        assert_eq!(buf.len(), n);
+
+        // Stops at the end of the input without waiting for the deadline.
+        buf.clear();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let n = read_buf_until(&mut &[1, 2, 3][..], &mut buf.unfilled(), deadline).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn read_with_progress() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[0]);
+
+        let mut counts = Vec::new();
+        let mut reader = Chunked(&[1; 10]);
+        let n =
+            read_buf_with_progress(&mut reader, &mut buf.unfilled(), |total| counts.push(total))
+                .unwrap();
+        assert_eq!(n, 10);
+        assert_eq!(counts, [4, 8, 10]);
+        assert_eq!(buf.len(), 11);
+    }
+
+    #[test]
+    fn dyn_read() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+
+        let mut input: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let reader: &mut dyn Read = &mut input;
+        assert_eq!(read_buf(reader, &mut buf.unfilled()).unwrap(), 6);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5, 6]);
+
+        let mut input: &[u8] = &[7, 8, 9];
+        let reader: &mut dyn Read = &mut input;
+        reader.read_buf_exact(&mut buf.unfilled()).unwrap();
+        assert_eq!(buf.filled(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(reader.read_buf(&mut buf.unfilled()).unwrap(), 0);
+
+        let mut input: &[u8] = &[1, 2];
+        let reader: &mut dyn Read = &mut input;
+        assert_eq!(read_array::<2, _>(reader).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn fill_vec() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut vec = vec![0];
+
+        assert_eq!(fill_vec_from(&mut vec, 4, &mut reader).unwrap(), 4);
+        assert_eq!(vec, [0, 1, 2, 3, 4]);
+        assert_eq!(fill_vec_from(&mut vec, 4, &mut reader).unwrap(), 2);
+        assert_eq!(fill_vec_from(&mut vec, 4, &mut reader).unwrap(), 0);
+        assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn read_header_array() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5];
+        let header: [u8; 4] = read_array(&mut reader).unwrap();
+        assert_eq!(header, [1, 2, 3, 4]);
+        assert_eq!(reader, &[5]);
+
+        let err = read_array::<4, _>(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_vectored_spanning_cursors() {
+        let mut a = [MaybeUninit::uninit(); 4];
+        let mut b = [MaybeUninit::uninit(); 8];
+        let mut a = BorrowBuf::from(&mut a[..]);
+        let mut b = BorrowBuf::from(&mut b[..]);
+        a.extend_from_slice(&[0]);
+
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let n = read_buf_vectored(&mut reader, &mut [a.unfilled(), b.unfilled()]).unwrap();
+        assert_eq!(n, 6);
+        assert_eq!(a.filled(), &[0, 1, 2, 3]);
+        assert_eq!(b.filled(), &[4, 5, 6]);
+        assert_eq!(b.init_len(), 8);
+    }
+}
@@ -0,0 +1,127 @@
+//! Reassembling a stream from segments which arrive out of order.
+
+use crate::BorrowBuf;
+use std::cmp;
+use std::ops::Range;
+
+/// A buffer which is written in segments at arbitrary offsets, and filled up to the first gap.
+///
+/// This is the core of a reassembly buffer for a protocol like TCP or QUIC: each segment is
+/// written at its offset with `write_at`, and `advance_contiguous` then fills the buffer over the
+/// segments which join up with the filled part. Offsets are relative to the start of the buffer,
+/// the same as for `BorrowBuf::filled`.
+#[derive(Debug)]
+pub struct ReassemblyBuf<'a> {
+    buf: BorrowBuf<'a>,
+    // Written but unfilled ranges, sorted, without overlaps, and not touching each other.
+    pending: Vec<Range<usize>>,
+}
+
+impl<'a> ReassemblyBuf<'a> {
+    /// Creates a new reassembly buffer, continuing after the filled part of `buf`.
+    pub fn new(buf: BorrowBuf<'a>) -> ReassemblyBuf<'a> {
+        ReassemblyBuf {
+            buf,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying buffer.
+    #[inline]
+    pub fn buf(&self) -> &BorrowBuf<'a> {
+        &self.buf
+    }
+
+    /// Returns the underlying buffer, forgetting any segments which are not yet filled.
+    #[inline]
+    pub fn into_inner(self) -> BorrowBuf<'a> {
+        self.buf
+    }
+
+    /// Returns the ranges of bytes which have been written but are not yet filled.
+    #[inline]
+    pub fn pending(&self) -> &[Range<usize>] {
+        &self.pending
+    }
+
+    /// Writes a segment at `offset`, see `BorrowBuf::write_at`.
+    ///
+    /// Bytes which are already filled are overwritten, as are bytes of earlier segments which
+    /// overlap this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset + data.len()` is greater than the capacity of the buffer.
+    #[track_caller]
+    pub fn write_at(&mut self, offset: usize, data: &[u8]) {
+        self.buf.write_at(offset, data);
+        if data.is_empty() {
+            return;
+        }
+
+        // Merge with every pending range which overlaps or touches the new one.
+        let mut range = offset..offset + data.len();
+        let first = self.pending.partition_point(|r| r.end < range.start);
+        let last = self.pending.partition_point(|r| r.start <= range.end);
+        for r in &self.pending[first..last] {
+            range.start = cmp::min(range.start, r.start);
+            range.end = cmp::max(range.end, r.end);
+        }
+        self.pending.splice(first..last, [range]);
+    }
+
+    /// Fills the buffer over the written segments which join up with its filled part.
+    ///
+    /// Returns the number of bytes by which the filled part grew.
+    pub fn advance_contiguous(&mut self) -> usize {
+        let len = self.buf.len();
+        let mut end = len;
+        let joined = self.pending.partition_point(|r| r.start <= end);
+        if let Some(last) = self.pending[..joined].last() {
+            end = cmp::max(end, last.end);
+        }
+        self.pending.drain(..joined);
+
+        // The bytes up to `end` were either filled already or written by `write_at`.
+        self.buf.filled = self.buf.head + end;
+        end - len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn out_of_order_segments() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = ReassemblyBuf::new(BorrowBuf::from(&mut backing[..]));
+
+        buf.write_at(6, b"ghi");
+        buf.write_at(3, b"def");
+        assert_eq!(buf.advance_contiguous(), 0);
+        assert_eq!(buf.pending(), &[3..9]);
+        assert_eq!(buf.buf().init_len(), 9);
+
+        buf.write_at(12, b"mn");
+        buf.write_at(0, b"abc");
+        assert_eq!(buf.advance_contiguous(), 9);
+        assert_eq!(buf.buf().filled(), b"abcdefghi");
+        assert_eq!(buf.pending(), &[12..14]);
+
+        buf.write_at(9, b"jkl");
+        assert_eq!(buf.advance_contiguous(), 5);
+        assert_eq!(buf.buf().filled(), b"abcdefghijklmn");
+        assert!(buf.pending().is_empty());
+    }
+
+    #[test]
+    fn write_at_zeroes_gap() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.write_at(4, &[1, 2]);
+        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.initialized(), &[0, 0, 0, 0, 1, 2]);
+    }
+}
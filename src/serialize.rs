@@ -0,0 +1,240 @@
+//! Serializing buffers.
+//!
+//! A buffer is serialized as a struct with its filled bytes and its initialized length,
+//! `{ filled: [u8], initialized: usize }`. Only the filled bytes are recorded; initialized but
+//! unfilled bytes are zeroed when deserialized. Borrowed buffers cannot be deserialized, so
+//! deserialization targets the owned `VecBuf`.
+
+use crate::owned::{OwnedBuf, OwnedCursor, VecBuf};
+use crate::{mem, BorrowBuf};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+const FIELDS: &[&str] = &["filled", "initialized"];
+
+fn serialize<S: Serializer>(
+    serializer: S,
+    filled: &[u8],
+    initialized: usize,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Buf", 2)?;
+    state.serialize_field("filled", filled)?;
+    state.serialize_field("initialized", &initialized)?;
+    state.end()
+}
+
+impl Serialize for BorrowBuf<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(serializer, self.filled(), self.init_len())
+    }
+}
+
+impl Serialize for VecBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(serializer, self.filled(), self.init_len())
+    }
+}
+
+// Rebuilds a `VecBuf` with `initialized - filled.len()` zeroed bytes of initialized spare capacity.
+fn build<E: de::Error>(mut filled: Vec<u8>, initialized: usize) -> Result<VecBuf, E> {
+    let len = filled.len();
+    if initialized < len {
+        return Err(E::invalid_value(
+            de::Unexpected::Unsigned(initialized as u64),
+            &"an initialized length of at least the filled length",
+        ));
+    }
+
+    // Reserve through the `Vec`, since `VecBuf::try_reserve` cannot fail and panics instead.
+    let extra = initialized - len;
+    if filled.try_reserve_exact(extra).is_err() {
+        return Err(E::invalid_value(
+            de::Unexpected::Unsigned(initialized as u64),
+            &"an initialized length which can be allocated",
+        ));
+    }
+
+    let mut buf = VecBuf::new(filled);
+    buf.try_reserve(extra).unwrap();
+    let mut cursor = buf.unfilled();
+    mem::zero(&mut cursor.uninit_mut()[..extra]);
+    // SAFETY: the first `extra` bytes of the cursor were zeroed above
+    unsafe {
+        cursor.set_init(extra);
+    }
+    Ok(buf)
+}
+
+enum Field {
+    Filled,
+    Initialized,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Field, D::Error> {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("`filled` or `initialized`")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Field, E> {
+                match value {
+                    "filled" => Ok(Field::Filled),
+                    "initialized" => Ok(Field::Initialized),
+                    _ => Err(E::unknown_field(value, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+struct VecBufVisitor;
+
+impl<'de> Visitor<'de> for VecBufVisitor {
+    type Value = VecBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a buffer with `filled` and `initialized` fields")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<VecBuf, A::Error> {
+        let filled = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let initialized = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        build(filled, initialized)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<VecBuf, A::Error> {
+        let mut filled = None;
+        let mut initialized = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Filled if filled.is_some() => {
+                    return Err(de::Error::duplicate_field("filled"))
+                }
+                Field::Filled => filled = Some(map.next_value()?),
+                Field::Initialized if initialized.is_some() => {
+                    return Err(de::Error::duplicate_field("initialized"))
+                }
+                Field::Initialized => initialized = Some(map.next_value()?),
+            }
+        }
+        let filled = filled.ok_or_else(|| de::Error::missing_field("filled"))?;
+        let initialized = initialized.ok_or_else(|| de::Error::missing_field("initialized"))?;
+        build(filled, initialized)
+    }
+}
+
+impl<'de> Deserialize<'de> for VecBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<VecBuf, D::Error> {
+        deserializer.deserialize_struct("Buf", FIELDS, VecBufVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, Token};
+    use std::mem::MaybeUninit;
+
+    // Compares the state which is serialized, since `VecBuf` is not `PartialEq`.
+    #[derive(Debug)]
+    struct State(VecBuf);
+
+    impl PartialEq for State {
+        fn eq(&self, other: &State) -> bool {
+            self.0.filled() == other.0.filled() && self.0.init_len() == other.0.init_len()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for State {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<State, D::Error> {
+            VecBuf::deserialize(deserializer).map(State)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.extend_from_slice(&[1, 2, 3]);
+        buf.write_at(3, &[0, 0]);
+
+        let tokens = [
+            Token::Struct {
+                name: "Buf",
+                len: 2,
+            },
+            Token::Str("filled"),
+            Token::Seq { len: Some(3) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::U8(3),
+            Token::SeqEnd,
+            Token::Str("initialized"),
+            Token::U64(5),
+            Token::StructEnd,
+        ];
+        assert_ser_tokens(&buf, &tokens);
+
+        let mut owned = VecBuf::new(vec![1, 2, 3]);
+        owned.try_reserve(2).unwrap();
+        let mut cursor = owned.unfilled();
+        cursor.uninit_mut()[..2].fill(MaybeUninit::new(0));
+        unsafe {
+            cursor.set_init(2);
+        }
+        assert_ser_tokens(&owned, &tokens);
+        assert_de_tokens(&State(owned), &tokens);
+    }
+
+    #[test]
+    fn initialized_shorter_than_filled() {
+        assert_de_tokens_error::<VecBuf>(
+            &[
+                Token::Struct {
+                    name: "Buf",
+                    len: 2,
+                },
+                Token::Str("filled"),
+                Token::Seq { len: Some(1) },
+                Token::U8(1),
+                Token::SeqEnd,
+                Token::Str("initialized"),
+                Token::U64(0),
+                Token::StructEnd,
+            ],
+            "invalid value: integer `0`, expected an initialized length of at least the filled length",
+        );
+    }
+
+    #[test]
+    fn initialized_too_large() {
+        assert_de_tokens_error::<VecBuf>(
+            &[
+                Token::Struct {
+                    name: "Buf",
+                    len: 2,
+                },
+                Token::Str("filled"),
+                Token::Seq { len: Some(1) },
+                Token::U8(1),
+                Token::SeqEnd,
+                Token::Str("initialized"),
+                Token::U64(u64::MAX),
+                Token::StructEnd,
+            ],
+            "invalid value: integer `18446744073709551615`, expected an initialized length which can be allocated",
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Cursors for reading secrets.
+
+use crate::BorrowCursor;
+use std::ops::{Deref, DerefMut};
+use zeroize::Zeroize;
+
+/// A cursor which zeroes the initialized but unfilled bytes in its view of the buffer when it is
+/// dropped.
+///
+/// Create one with [`BorrowCursor::zeroing`]. It can be used like the wrapped cursor, and is
+/// intended for reads of secrets: if a reader writes more into the cursor than it ends up
+/// advancing (e.g., a failed or partial read), the leftover bytes do not linger in the buffer's
+/// spare capacity. Filled bytes are left alone.
+///
+/// The guarantee only covers bytes the buffer knows are initialized. Bytes written through
+/// `as_mut` or `uninit_mut` without a matching `set_init` or `advance` are not tracked, so they
+/// are not zeroed. Zeroing uses volatile writes, so it is not optimized away.
+#[derive(Debug)]
+pub struct ZeroingCursor<'a, 'b> {
+    cursor: BorrowCursor<'a, 'b>,
+}
+
+impl<'a, 'b> ZeroingCursor<'a, 'b> {
+    pub(crate) fn new(cursor: BorrowCursor<'a, 'b>) -> ZeroingCursor<'a, 'b> {
+        ZeroingCursor { cursor }
+    }
+}
+
+impl<'a, 'b> Deref for ZeroingCursor<'a, 'b> {
+    type Target = BorrowCursor<'a, 'b>;
+
+    fn deref(&self) -> &BorrowCursor<'a, 'b> {
+        &self.cursor
+    }
+}
+
+impl<'a, 'b> DerefMut for ZeroingCursor<'a, 'b> {
+    fn deref_mut(&mut self) -> &mut BorrowCursor<'a, 'b> {
+        &mut self.cursor
+    }
+}
+
+impl Drop for ZeroingCursor<'_, '_> {
+    fn drop(&mut self) {
+        self.cursor.init_mut().zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BorrowBuf;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn zero_leftovers_on_drop() {
+        let mut backing = [MaybeUninit::uninit(); 16];
+        let mut buf = BorrowBuf::from(&mut backing[..]);
+        buf.unfilled().append(b"hdr");
+
+        {
+            let mut cursor = buf.unfilled().zeroing();
+            // As if a reader wrote 8 secret bytes but only 4 of them were accepted.
+            cursor.append(b"secr");
+            cursor.poke(b"et!!");
+        }
+
+        assert_eq!(buf.filled(), b"hdrsecr");
+        assert_eq!(buf.init_len(), 11);
+        assert_eq!(&buf.initialized()[7..], &[0; 4]);
+    }
+}
@@ -0,0 +1,8 @@
+//! Checks that misuse of buffers and cursors which would be unsound is rejected by the borrow
+//! checker.
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,32 @@
+//! Checks that panics in cursor methods are reported at the caller.
+//!
+//! This replaces the global panic hook, so it runs in its own test binary rather than alongside
+//! the unit tests.
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn panic_location_is_caller() {
+    let mut backing = [MaybeUninit::uninit(); 2];
+    let mut buf = BorrowBuf::from(&mut backing[..]);
+
+    let location = Arc::new(Mutex::new(None));
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new({
+        let location = location.clone();
+        move |info| {
+            *location.lock().unwrap() = info
+                .location()
+                .map(|location| (location.file().to_owned(), location.line()));
+        }
+    }));
+    let line = line!() + 1;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| buf.unfilled().append(&[1, 2, 3])));
+    panic::set_hook(hook);
+
+    assert!(result.is_err());
+    assert_eq!(*location.lock().unwrap(), Some((file!().to_owned(), line)));
+}
@@ -0,0 +1,13 @@
+// The filled part of a buffer must not be read while a cursor could advance over it.
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+
+fn main() {
+    let mut backing = [MaybeUninit::uninit(); 8];
+    let mut buf = BorrowBuf::from(&mut backing[..]);
+    let mut cursor = buf.unfilled();
+    let filled = buf.filled();
+    cursor.append(&[1]);
+    println!("{:?}", filled);
+}
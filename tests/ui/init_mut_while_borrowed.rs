@@ -0,0 +1,15 @@
+// The initialized part of a cursor must not be mutably borrowed twice, e.g., while also looking at
+// the uninitialized part, which `ensure_init` could turn into initialized bytes.
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+
+fn main() {
+    let mut backing = [MaybeUninit::uninit(); 8];
+    let mut buf = BorrowBuf::from(&mut backing[..]);
+    let mut cursor = buf.unfilled();
+    let init = cursor.init_mut();
+    let uninit = cursor.uninit_mut();
+    init.fill(0);
+    uninit[0].write(1);
+}
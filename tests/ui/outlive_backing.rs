@@ -0,0 +1,13 @@
+// A buffer must not outlive the memory it borrows.
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+
+fn main() {
+    let buf;
+    {
+        let mut backing = [MaybeUninit::uninit(); 8];
+        buf = BorrowBuf::from(&mut backing[..]);
+    }
+    println!("{}", buf.len());
+}
@@ -0,0 +1,13 @@
+// Two cursors over one buffer could both write to the same unfilled bytes.
+
+use read_buf::BorrowBuf;
+use std::mem::MaybeUninit;
+
+fn main() {
+    let mut backing = [MaybeUninit::uninit(); 8];
+    let mut buf = BorrowBuf::from(&mut backing[..]);
+    let mut a = buf.unfilled();
+    let mut b = buf.unfilled();
+    a.append(&[1]);
+    b.append(&[2]);
+}